@@ -0,0 +1,66 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/**
+ * Configuration of the scanner across all benchmarks.
+ */
+const DATA_SIZES: [u64; 3] = [1000, 100000, 5000000];
+const NUM_THREADS: usize = 4;
+
+/**
+ * The system allocator, counting every allocation made through it, from any thread.
+ */
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+
+/**
+ * The hillis steel scan maps over the pool once per step, so how much it allocates per scan, apart from the input, shows
+ * what the pool costs on every dispatch.  The allocations of a scan, after one to warm up the scanner's workspace, are
+ * printed before timing it.
+ */
+fn hillis_steel_allocation_bench(c: &mut Criterion) {
+    let mut scanner = prefix_scan::Scanner::new().with_threads(NUM_THREADS);
+
+    let mut group = c.benchmark_group("hillis steel allocation bench");
+    for size in DATA_SIZES {
+        let vec = (0..size).collect::<Vec<u64>>();
+        scanner.hillis_steel_scan(vec.clone()).unwrap();
+
+        let data = vec.clone();
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        scanner.hillis_steel_scan(data).unwrap();
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+        println!("hillis steel scan of {} elements: {} allocations", size, allocations);
+
+        group.throughput(criterion::Throughput::Bytes(8 * size));
+        group.bench_with_input(criterion::BenchmarkId::from_parameter(size), &size, |b, _| {
+            b.iter_batched(
+                || vec.clone(),
+                |data| scanner.hillis_steel_scan(data).unwrap(),
+                criterion::BatchSize::LargeInput
+            )
+        });
+    }
+}
+
+criterion_group!(allocations, hillis_steel_allocation_bench);
+
+criterion_main!(allocations);
@@ -0,0 +1,104 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+/**
+ * Configuration of the scanner across all benchmarks.
+ */
+const DATA_SIZES: [u64; 3] = [500000, 5000000, 32000000];
+const CACHE_CHUNK_LENGTH: usize = 250000;
+const NUM_THREADS: usize = 8;
+const SEQUENTIAL_LENGTH: usize = 10000;
+
+type ComparisonCaller = Box<dyn FnMut(Vec<u64>) -> Vec<u64>>;
+
+
+/**
+ * The plainest scan there is, a fold over the data with the standard library, writing each running total back.
+ */
+fn std_fold_scan(mut data: Vec<u64>) -> Vec<u64> {
+    std_fold_scan_slice(&mut data);
+    data
+}
+
+/**
+ * The fold over a slice, returning its total.
+ */
+fn std_fold_scan_slice(data: &mut [u64]) -> u64 {
+    data.iter_mut().fold(0, |acc, x| {
+        *x = x.wrapping_add(acc);
+        *x
+    })
+}
+
+/**
+ * The same two sweeps as `divide_and_conquer_scan`, written the straightforward way with rayon, on its own pool of the
+ * same number of threads.  Each chunk is scanned in parallel, the chunks' totals are scanned into their carries, and the
+ * carries are added back onto the chunks in parallel.
+ */
+#[cfg(feature = "rayon")]
+fn rayon_scan(pool: &rayon::ThreadPool, mut data: Vec<u64>) -> Vec<u64> {
+    use rayon::prelude::*;
+
+    let chunk_len = std::cmp::max(1, data.len().div_ceil(NUM_THREADS));
+    pool.install(|| {
+        let totals = data.par_chunks_mut(chunk_len).map(std_fold_scan_slice).collect::<Vec<_>>();
+        let carries = totals.iter().scan(0u64, |carry, total| {
+            let previous = *carry;
+            *carry = carry.wrapping_add(*total);
+            Some(previous)
+        }).collect::<Vec<_>>();
+        data.par_chunks_mut(chunk_len).zip(carries).skip(1).for_each(|(chunk, carry)| {
+            chunk.iter_mut().for_each(|x| *x = x.wrapping_add(carry));
+        });
+    });
+    data
+}
+
+/**
+ * Runs this crate's divide and conquer scan against the baselines on the same data at each size, in one group per
+ * size, so a regression shows up relative to the baselines rather than as a change in absolute time.  The rayon
+ * baseline needs the rayon feature, `cargo bench --bench comparison_benches --features rayon`.
+ */
+fn comparison_bench(c: &mut Criterion) {
+    let mut scanner = prefix_scan::Scanner::new()
+        .with_threads(NUM_THREADS)
+        .with_cache_chunk_length(CACHE_CHUNK_LENGTH)
+        .with_sequential_length(SEQUENTIAL_LENGTH);
+
+    // vector of pairs of (algorithm name, algorithm caller)
+    #[allow(unused_mut)]
+    let mut scan_algorithms: Vec<(&str, ComparisonCaller)> = vec![
+        ("divide conquer post scatter bench", Box::new(move |data| scanner.divide_and_conquer_scan(data).unwrap())),
+        ("std fold bench", Box::new(std_fold_scan))
+    ];
+    #[cfg(feature = "rayon")]
+    {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(NUM_THREADS).build().unwrap();
+        scan_algorithms.push(("rayon bench", Box::new(move |data| rayon_scan(&pool, data))));
+    }
+
+    for size in DATA_SIZES {
+        let mut group = c.benchmark_group(format!("comparison bench {}", size));
+        group.throughput(criterion::Throughput::Bytes(8 * size));
+        if size > 5000000 {
+            group.sample_size(10);
+        }
+
+        let vec = (0..size).collect::<Vec<u64>>();
+        let expected = std_fold_scan(vec.clone());
+        for (name, caller) in scan_algorithms.iter_mut() {
+            // make sure every scan is computing the same thing before timing any of them
+            assert_eq!(caller(vec.clone()), expected, "{} disagrees with the baseline", name);
+            group.bench_function(criterion::BenchmarkId::from_parameter(*name), |b| {
+                b.iter_batched(
+                    || vec.clone(),
+                    &mut *caller,
+                    criterion::BatchSize::LargeInput
+                )
+            });
+        }
+    }
+}
+
+criterion_group!(comparisons, comparison_bench);
+
+criterion_main!(comparisons);
@@ -1,11 +1,12 @@
 use criterion::{criterion_group, criterion_main, Criterion};
-use prefix_scan;
 
 const DATA_SIZE: u64 = 5000000;
 const NUM_THREADS: usize = 4;
 const SEQUENTIAL_LENGTH: usize = 10000;
+const PARALLEL_DATA_SIZE: u64 = 150000;
+const SMALL_DATA_SIZE: u64 = 1000;
 
-/**
+/*
  * Benchmarks tuning the individual parameters of the scanner to find which values are most efficient.
  */
 
@@ -47,15 +48,54 @@ fn tune_sequential_length_bench(c: &mut Criterion) {
     }    
 }
 
+fn small_input_sequential_length_bench(c: &mut Criterion) {
+    let vec = (0..SMALL_DATA_SIZE).collect::<Vec<u64>>();
+
+    // with a sequential length above the input size, the divide and conquer scan shouldn't touch the thread pool at all
+    let mut group = c.benchmark_group("small input sequential length bench");
+    for sequential_length in [0, SEQUENTIAL_LENGTH] {
+        let mut scanner = prefix_scan::Scanner::new()
+            .with_threads(NUM_THREADS)
+            .with_sequential_length(sequential_length);
+        group.throughput(criterion::Throughput::Bytes(8 * SMALL_DATA_SIZE));
+        group.bench_with_input(criterion::BenchmarkId::from_parameter(sequential_length), &sequential_length, |b, _| {
+            b.iter_batched(
+                || vec.clone(),
+                |data| scanner.divide_and_conquer_scan(data),
+                criterion::BatchSize::SmallInput
+            )
+        });
+    }    
+}
+
+fn tune_parallel_length_bench(c: &mut Criterion) {
+    let mut scanner = prefix_scan::Scanner::new()
+        .with_threads(NUM_THREADS);
+    // the parallel length only matters for smaller inputs, so use a dataset around the size of the lengths being tuned
+    let vec = (0..PARALLEL_DATA_SIZE).collect::<Vec<u64>>();
+    
+    let mut group = c.benchmark_group("tune parallel length bench");
+    for parallel_length in (0..10).map(|i| i * 25000) {
+        group.throughput(criterion::Throughput::Bytes(8 * PARALLEL_DATA_SIZE));
+        group.bench_with_input(criterion::BenchmarkId::from_parameter(parallel_length), &parallel_length, |b, &parallel_length| {
+            scanner.set_parallel_length(parallel_length);
+            b.iter_batched(
+                || vec.clone(),
+                |data| scanner.scan(data),
+                criterion::BatchSize::LargeInput
+            )
+        });
+    }    
+}
+
 fn tune_num_threads_bench(c: &mut Criterion) {
     let vec = (0..DATA_SIZE).collect::<Vec<u64>>();
     
     let mut group = c.benchmark_group("tune num threads bench");
+    let mut scanner = prefix_scan::Scanner::new().with_sequential_length(SEQUENTIAL_LENGTH);
     for num_threads in 1..10 {
         group.throughput(criterion::Throughput::Bytes(8 * DATA_SIZE));
-        let mut scanner = prefix_scan::Scanner::new()
-            .with_threads(num_threads)
-            .with_sequential_length(SEQUENTIAL_LENGTH);
+        scanner.set_threads(num_threads);
         group.bench_with_input(criterion::BenchmarkId::from_parameter(num_threads), &num_threads, |b, _| {
             b.iter_batched(
                 || vec.clone(),
@@ -66,9 +106,31 @@ fn tune_num_threads_bench(c: &mut Criterion) {
     }    
 }
 
+fn thread_affinity_bench(c: &mut Criterion) {
+    let vec = (0..DATA_SIZE).collect::<Vec<u64>>();
+    
+    let mut group = c.benchmark_group("thread affinity bench");
+    for thread_affinity in [false, true] {
+        group.throughput(criterion::Throughput::Bytes(8 * DATA_SIZE));
+        let mut scanner = prefix_scan::Scanner::new()
+            .with_threads(NUM_THREADS)
+            .with_thread_affinity(thread_affinity);
+        group.bench_with_input(criterion::BenchmarkId::from_parameter(thread_affinity), &thread_affinity, |b, _| {
+            b.iter_batched(
+                || vec.clone(),
+                |data| scanner.divide_and_conquer_scan(data),
+                criterion::BatchSize::LargeInput
+            )
+        });
+    }    
+}
+
 criterion_group!(parameter_tuning_benches, 
     tune_cache_chunk_length_bench, 
     tune_sequential_length_bench,
-    tune_num_threads_bench
+    small_input_sequential_length_bench,
+    tune_parallel_length_bench,
+    tune_num_threads_bench,
+    thread_affinity_bench
 );
 criterion_main!(parameter_tuning_benches);
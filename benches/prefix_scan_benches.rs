@@ -1,5 +1,4 @@
 use criterion::{criterion_group, criterion_main, Criterion};
-use prefix_scan;
 
 /**
  * Configuration of the scanner across all benchmarks.
@@ -9,6 +8,8 @@ const CACHE_CHUNK_LENGTH: usize = 250000;
 const NUM_THREADS: usize = 8;
 const SEQUENTIAL_LENGTH: usize = 10000;
 
+type ScanCaller = fn(&mut prefix_scan::Scanner, Vec<u64>) -> Result<Vec<u64>, prefix_scan::ScanError>;
+
 
 /**
  * Iterates over each of the different scan algorithms and runs them on the same dataset in the same benchmark group for easy comparison.
@@ -21,7 +22,8 @@ fn prefix_scans_bench(c: &mut Criterion) {
         .with_sequential_length(SEQUENTIAL_LENGTH);
 
     // vector of pairs of (algorithm name, algorithm caller)
-    let scan_algorithms: Vec<(&str, fn(&mut prefix_scan::Scanner, Vec<u64>) -> Result<Vec<u64>, prefix_scan::ScanError>)> = vec![
+    let scan_algorithms: Vec<(&str, ScanCaller)> = vec![
+        ("auto dispatch bench", |scanner, data| scanner.scan(data)),
         ("divide conquer post scatter bench", |scanner, data| scanner.divide_and_conquer_scan(data)),
         ("hillis steel bench", |scanner, data| scanner.hillis_steel_scan(data)),
         ("blelloch bench", |scanner, data| scanner.blelloch_scan(data)),
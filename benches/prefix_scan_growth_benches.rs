@@ -1,5 +1,4 @@
 use criterion::{criterion_group, criterion_main, Criterion};
-use prefix_scan;
 
 /**
  * Configuration of the scanner across all benchmarks.
@@ -54,8 +53,38 @@ fn sequential_baseline_bench(c: &mut Criterion) {
     scan_benchmark(c, "sequential baseline bench", |_, mut data| Ok(prefix_scan::baseline::sequential_scan_simd(&mut data)).map(|_| data))
 }
 
+/**
+ * Scaling of the divide and conquer scan over the thread count on a large input, with the cache chunks scanned one
+ * after another, and with them handed out to the threads as parallel tiles.
+ */
+fn parallel_tiles_bench(c: &mut Criterion) {
+    let size = 128 * CHUNK_SIZE;
+    let vec = (0..size).collect::<Vec<u64>>();
+
+    let mut group = c.benchmark_group("parallel tiles bench");
+    group.sample_size(10);
+    group.throughput(criterion::Throughput::Bytes(8 * size));
+    for num_threads in [1, 2, 4, 8] {
+        for parallel_tiles in [false, true] {
+            let mut scanner = prefix_scan::Scanner::new()
+                .with_threads(num_threads)
+                .with_cache_chunk_length(CACHE_CHUNK_LENGTH)
+                .with_parallel_tiles(parallel_tiles);
+            let id = format!("{} threads{}", num_threads, if parallel_tiles { " tiled" } else { "" });
+            group.bench_function(criterion::BenchmarkId::from_parameter(id), |b| {
+                b.iter_batched(
+                    || vec.clone(),
+                    |data| scanner.divide_and_conquer_scan(data).unwrap(),
+                    criterion::BatchSize::LargeInput
+                )
+            });
+        }
+    }
+}
+
 criterion_group!(prefix_scan_benches, 
     divide_and_conquer_bench, 
+    parallel_tiles_bench,
     blelloch_bench,
     hillis_steel_bench,
     sequential_baseline_bench
@@ -1,8 +1,7 @@
 use criterion::{criterion_group, criterion_main, Criterion};
-use prefix_scan;
 
 
-/**
+/*
  * Some benchmarks for various simd functions used as building blocks by the prefix scans for speedup.
  */
 
@@ -15,33 +14,63 @@ fn sequential_simd_bench(c: &mut Criterion) {
     });
 }
 
+fn simd_width_bench(c: &mut Criterion) {
+    use prefix_scan::prefix_scans::helper_functions::{self, SimdWidth};
+    let mut group = c.benchmark_group("simd width");
+    for width in [SimdWidth::Lanes4, SimdWidth::Lanes8, SimdWidth::Lanes16] {
+        let mut vec = (0..LARGE_COUNT).collect::<Vec<_>>();
+        group.bench_with_input(criterion::BenchmarkId::from_parameter(width.lanes()), &width, |b, &width| {
+            b.iter(|| helper_functions::prefix_scan_simd_width(&mut vec, 0, width))
+        });
+    }
+}
+
 fn quicksum_simd_bench(c: &mut Criterion) {
     c.bench_function("quicksum simd", |b| {
-        let mut vec = (0..LARGE_COUNT).collect::<Vec<_>>();
-        b.iter(move || prefix_scan::helper_functions::quicksum_simd(&mut vec))
+        let vec = (0..LARGE_COUNT).collect::<Vec<_>>();
+        b.iter(move || prefix_scan::quicksum_simd(&vec))
+    });
+}
+
+fn quicksum_simd_tail_bench(c: &mut Criterion) {
+    c.bench_function("quicksum simd tail", |b| {
+        // short slices with 7 elements left over, so the tail is nearly half of the work
+        let vec = (0..LARGE_COUNT).collect::<Vec<_>>();
+        b.iter(|| vec.chunks(15).map(prefix_scan::quicksum_simd).fold(0, u64::wrapping_add))
     });
 }
 
 fn sequential_no_simd_bench(c: &mut Criterion) {
     c.bench_function("sequential no simd", |b| {
         let mut vec = (0..LARGE_COUNT).collect::<Vec<_>>();
-        b.iter(move || vec = prefix_scan::baseline::sequential_scan_no_simd(std::mem::replace(&mut vec, vec![]), |a, b| a + b).unwrap())
+        b.iter(move || vec = prefix_scan::baseline::sequential_scan_no_simd(std::mem::take(&mut vec), |a, b| a + b).unwrap())
     });
 }
 
 fn parallel_simd_quicksum_bench(c: &mut Criterion) {
     c.bench_function("parallel quicksum simd", |b| {
-        let mut vec = (0..LARGE_COUNT).collect::<Vec<_>>();
+        let vec = (0..LARGE_COUNT).collect::<Vec<_>>();
+        let mut scanner = prefix_scan::Scanner::new().with_threads(4);
+        b.iter(move || scanner.parallel_quicksum_simd(&vec).unwrap())
+    });
+}
+
+fn parallel_reduce_sum_bench(c: &mut Criterion) {
+    c.bench_function("parallel reduce sum", |b| {
+        let vec = (0..LARGE_COUNT).collect::<Vec<_>>();
         let mut scanner = prefix_scan::Scanner::new().with_threads(4);
-        b.iter(move || scanner.parallel_quicksum_simd(&mut vec))
+        b.iter(move || scanner.parallel_reduce_sum(&vec).unwrap())
     });
 }
 
-criterion_group!(simd_benches, 
+criterion_group!(simd_benches,
+    simd_width_bench, 
     sequential_simd_bench, 
     quicksum_simd_bench, 
+    quicksum_simd_tail_bench,
     sequential_no_simd_bench, 
-    parallel_simd_quicksum_bench
+    parallel_simd_quicksum_bench,
+    parallel_reduce_sum_bench
 );
 
 criterion_main!(simd_benches);
\ No newline at end of file
@@ -0,0 +1,60 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use prefix_scan::util::thread_pool;
+
+const NUM_THREADS: usize = 4;
+const NUM_TASKS: usize = 64;
+
+/*
+ * Benchmarks of the thread pool on its own, apart from any scan.
+ */
+
+/**
+ * Spins for roughly the given number of iterations, without being optimized away.
+ */
+fn spin(iterations: u64) -> u64 {
+    (0..iterations).fold(0, |acc: u64, i| criterion::black_box(acc.wrapping_add(i)))
+}
+
+/**
+ * Every eighth task costs ten times as much as the rest, so with a fixed round-robin assignment some threads get all of
+ * the expensive tasks, and the rest sit idle.
+ */
+fn skewed_tasks_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("skewed tasks bench");
+    for work_stealing in [false, true] {
+        let mut pool = thread_pool::ThreadPool::new(NUM_THREADS);
+        pool.set_work_stealing(work_stealing);
+        group.bench_with_input(criterion::BenchmarkId::from_parameter(work_stealing), &work_stealing, |b, _| {
+            b.iter(|| {
+                let costs = (0..NUM_TASKS).map(|i| if i % 8 == 0 { 100000 } else { 10000 }).collect::<Vec<u64>>();
+                pool.sendall(costs, |_, cost| spin(cost)).gather().unwrap()
+            })
+        });
+    }
+}
+
+/**
+ * The same skewed tasks, on the native pool with and without work stealing, and on rayon.
+ */
+#[cfg(feature = "rayon")]
+fn rayon_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rayon bench");
+    for backend in ["native", "stealing", "rayon"] {
+        let mut pool = thread_pool::ThreadPool::new(NUM_THREADS);
+        pool.set_work_stealing(backend == "stealing");
+        pool.set_rayon(backend == "rayon");
+        group.bench_with_input(criterion::BenchmarkId::from_parameter(backend), &backend, |b, _| {
+            b.iter(|| {
+                let costs = (0..NUM_TASKS).map(|i| if i % 8 == 0 { 100000 } else { 10000 }).collect::<Vec<u64>>();
+                pool.sendall(costs, |_, cost| spin(cost)).gather().unwrap()
+            })
+        });
+    }
+}
+
+#[cfg(not(feature = "rayon"))]
+criterion_group!(thread_pool_benches, skewed_tasks_bench);
+#[cfg(feature = "rayon")]
+criterion_group!(thread_pool_benches, skewed_tasks_bench, rayon_bench);
+criterion_main!(thread_pool_benches);
@@ -1,4 +1,26 @@
+#![cfg_attr(feature = "portable_simd", feature(portable_simd))]
+
+/*!
+ * Parallel prefix scans.  Everything in `prefix_scans` is re-exported at the top of the crate, so the scanner, its
+ * errors and the sequential baselines all have stable paths.  The simd sums are re-exported there too, and that's the
+ * path to use them by, rather than through `helper_functions`:
+ *
+ * ```
+ * use prefix_scan::{Scanner, ScanError, baseline, quicksum_simd, reduce_simd_sum};
+ *
+ * let data = (0..1000).collect::<Vec<u64>>();
+ * let expected = baseline::sequential_scan_no_simd(data.clone(), |a, b| a + b).unwrap();
+ *
+ * let mut scanner = Scanner::new().with_threads(4);
+ * let scanned: Result<Vec<u64>, ScanError> = scanner.divide_and_conquer_scan(data.clone());
+ * assert_eq!(scanned.unwrap(), expected);
+ * assert_eq!(quicksum_simd(&data), *expected.last().unwrap());
+ * assert_eq!(reduce_simd_sum(&[-1i64, 2, -3]), -2);
+ * ```
+ */
+
 pub mod prefix_scans;
 pub mod util;
 
-pub use prefix_scans::*;
\ No newline at end of file
+pub use prefix_scans::*;
+pub use prefix_scans::helper_functions::{quicksum_simd, reduce_simd_sum};
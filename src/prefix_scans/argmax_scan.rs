@@ -0,0 +1,87 @@
+use crate::prefix_scans::{Scanner, ScanError};
+use crate::util::split_vector;
+
+
+/**
+ * What each thread finds while scanning its chunk in the first sweep, (total, max prefix, index of the max prefix).  The
+ * max and its index are local to the chunk.
+ */
+type ChunkPartial = (i64, i64, usize);
+
+/**
+ * Scans the chunk in place, wrapping on overflow, and finds the earliest index of its largest prefix.
+ */
+fn scan_chunk_argmax(chunk: &mut [i64]) -> ChunkPartial {
+    let mut acc = 0i64;
+    let mut max = (i64::MIN, 0);
+    for (i, x) in chunk.iter_mut().enumerate() {
+        acc = acc.wrapping_add(*x);
+        *x = acc;
+        if acc > max.0 {
+            max = (acc, i);
+        }
+    }
+
+    (acc, max.0, max.1)
+}
+
+impl Scanner {
+    /**
+     * Scans the data, and also finds the index of the largest prefix sum, such as for finding the peak before a maximum
+     * drawdown.  Ties go to the earliest index, and there's no index for no data.  Each thread scans its chunk in the
+     * first sweep, keeping track of where its chunk's largest prefix is.  Adding a carry onto the whole chunk doesn't
+     * change where that is, so once the carries are known, the largest prefix overall is the largest of the chunks'
+     * largest prefixes plus their carries, without waiting on the second sweep.  Like the scans, sums wrap on overflow.
+     */
+    pub fn scan_argmax(&mut self, data: Vec<i64>) -> Result<(Vec<i64>, Option<usize>), ScanError> {
+        let ranges = self.chunk_offsets(data.len())?;
+        let mut data = split_vector::SplitVector::with_vec(data);
+
+        let chunks = data.chunk(&ranges)?;
+        let partials = self.map_on_executor(chunks, |_, mut chunk| scan_chunk_argmax(&mut chunk))?;
+
+        let mut carries = Vec::with_capacity(partials.len());
+        let mut carry = 0i64;
+        let mut argmax: Option<(i64, usize)> = None;
+        for ((total, max, index), start) in partials.into_iter().zip(ranges.iter()) {
+            let max = max.wrapping_add(carry);
+            if argmax.is_none_or(|(best, _)| max > best) {
+                argmax = Some((max, start + index));
+            }
+            carries.push(carry);
+            carry = carry.wrapping_add(total);
+        }
+
+        // nothing is carried into the first chunk, so it's already done
+        let chunks = data.chunk(&ranges)?.into_iter().zip(carries).skip(1).collect::<Vec<_>>();
+        self.map_on_executor(chunks, |_, (mut chunk, carry)| {
+            chunk.iter_mut().for_each(|x| *x = x.wrapping_add(carry));
+        })?;
+
+        let data = data.extract().ok_or(ScanError::BrokenThreadLocking)?;
+        Ok((data, argmax.map(|(_, index)| index)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prefix_scans;
+
+    #[test]
+    fn scan_argmax_test() {
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4);
+        let (scanned, argmax) = scanner.scan_argmax(vec![1, 2, -1, 5, -3, -4, 2, 1]).unwrap();
+        assert_eq!(scanned, vec![1, 3, 2, 7, 4, 0, 2, 3]);
+        assert_eq!(argmax, Some(3));
+        assert_eq!(scanner.scan_argmax(vec![]).unwrap(), (vec![], None));
+
+        // a rise, then a fall, then a rise back up to the same peak, which the earlier index wins
+        let data = (0..1003).map(|i| if i < 400 { 1 } else if i < 700 { -1 } else if i < 1000 { 1 } else { -1 }).collect::<Vec<i64>>();
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(data.clone(), |a, b| a + b).unwrap();
+        for num_threads in [1, 3, 8] {
+            let (scanned, argmax) = prefix_scans::Scanner::new().with_threads(num_threads).scan_argmax(data.clone()).unwrap();
+            assert_eq!(scanned, baseline);
+            assert_eq!(argmax, Some(399));
+        }
+    }
+}
@@ -3,6 +3,18 @@ use crate::prefix_scans::helper_functions;
 use crate::util::split_vector;
 
 
+/**
+ * The work sent to each thread in a sweep of the generic scan, (step, chunk, operator).
+ */
+type SweepWork<T> = (usize, split_vector::SplitVectorChunk<'static, T>, fn(&T, &T) -> T);
+
+/**
+ * An iterator over the steps up the pyramid for a vector of the given length (1 2 4 8 ...)
+ */
+fn pyramid_steps(vec_len: usize) -> impl DoubleEndedIterator<Item = usize> {
+    (0..((vec_len as f64).log2().ceil() as usize)).map(|i| 1 << i)
+}
+
 /**
  * This function, given the current step size of the pyramid, total width and number of threads, returns
  * which ranges the thread's chunks should be.  Used in both the up and down sweep loops for allocating
@@ -14,16 +26,17 @@ fn pyramid_ranges_for(step: usize, vec_len: usize, num_threads: usize, sequentia
     // the total number of operations to perform this step
     let mut num_operations = num_operands / 2;
     // if there is an extra operand, and left over values, they can be combined in another operation
-    if num_operands % 2 == 1 && vec_len % (step * 2) > 0 {
+    if num_operands % 2 == 1 && !vec_len.is_multiple_of(step * 2) {
         num_operations += 1;
     }
 
     /*
      * The sequential_length parameter specifies a point after which everything should be sequential, because the overhead of
-     * deploying to separate threads is not worth it anymore.
+     * deploying to separate threads is not worth it anymore.  The whole step is then one chunk, from the first operation to
+     * the end of the vector, so it still needs both offsets.
      */
     if num_operations < sequential_length {
-        return vec![step - 1];    
+        return vec![step - 1, vec_len];
     }
 
     let operation_ranges = if num_operations > num_threads {
@@ -37,25 +50,22 @@ fn pyramid_ranges_for(step: usize, vec_len: usize, num_threads: usize, sequentia
     let mut ranges = operation_ranges.into_iter().map(|chunk_start| chunk_start * step * 2 + step - 1).collect::<Vec<_>>();
     // make sure the last element rounds down to the size of the vector
     *ranges.last_mut().unwrap() = vec_len;
-    return ranges
+    ranges
 }
 
 impl Scanner {
-    pub fn blelloch_scan_generic<T: Default + Send + Sync + 'static>(&mut self, v: Vec<T>, func: fn(&T, &T) -> T) -> Result<Vec<T>, ScanError> {
-        let mut result_vec = split_vector::SplitVector::with_vec(v);
-    
-        // an iterator over the steps up the pyramid (1 2 4 8 ...)
-        let steps = (0..((result_vec.len() as f64).log2().ceil() as usize)).map(|i| 1 << i);
-    
-        /*
-         * First, we build up the pyramid of sections for which we know the total scans
-         */
-        for step in steps.clone() {
+    /**
+     * The first phase of the blelloch scan.  Builds up the pyramid of partial reductions in place, so that the peak of
+     * each sub pyramid holds the reduction of every element beneath it.  Once it's done, the last element holds the
+     * reduction of the whole vector.
+     */
+    pub fn blelloch_upsweep<T: Default + Send + Sync + 'static>(&mut self, data: &mut split_vector::SplitVector<T>, func: fn(&T, &T) -> T) -> Result<(), ScanError> {
+        for step in pyramid_steps(data.len()) {
             // split the vector into chunks based on the pyramid ranges for the current step
-            let ranges = pyramid_ranges_for(step, result_vec.len(), self.num_threads(), self.sequential_length);
-            let chunks = result_vec.chunk(&ranges).ok_or(ScanError::InvalidChunking)?.into_iter().map(|i| (step, i, func)).collect::<Vec<_>>();
+            let ranges = pyramid_ranges_for(step, data.len(), self.num_threads(), self.sequential_length);
+            let chunks = data.chunk(&ranges)?.into_iter().map(|i| (step, i, func)).collect::<Vec<_>>();
             // distribute the chunks and await results
-            self.thread_pool.sendall(chunks, |_, (step, mut chunk, func): (usize, split_vector::SplitVectorChunk<T>, fn(&T, &T) -> T)| {
+            self.map_on_executor(chunks, |_, (step, mut chunk, func): SweepWork<T>| {
                 /*
                  * Iterate through the chunks by step * 2, skipping every other element.  should look like
                  * a  b  c  d  ...
@@ -84,106 +94,37 @@ impl Scanner {
                     let result = func(&chunk[i], &chunk[pair]);
                     chunk[pair] = result;
                 }
-            }).gather().map_err(|_| ScanError::FailedThreadInGather)?;
-        }
-    
-        /*
-         * Next, convert the pyramid such that each section's peak has the sum of all elements that came before the section.  The topmost peak
-         * should therefore be 0
-         */
-        let len = result_vec.len();
-        result_vec.view_mut().ok_or(ScanError::BrokenThreadLocking)?[len - 1] = T::default();
-    
-        /*
-         * Iterate back down the pyramid, and fix each pyramid's peak to be the sum of all previous elements.  Do this by taking the left 
-         * sub pyramid's peak, swapping with current peak (same elements came before left pyramid as current pyramid), and set right 
-         * sub pyramid's peak to the sum of both.
-         */
-        for step in steps.clone().rev() {
-            let ranges = pyramid_ranges_for(step, result_vec.len(), self.num_threads(), self.sequential_length);
-            let chunks = result_vec.chunk(&ranges).ok_or(ScanError::InvalidChunking)?.into_iter().map(|i| (step, i, func)).collect::<Vec<_>>();
-            self.thread_pool.sendall(chunks, |_, (step, mut chunk, func): (usize, split_vector::SplitVectorChunk<T>, fn(&T, &T) -> T)| {
-                for i in (0..chunk.len()).step_by(step * 2) {
-                    let pair = if i + step < chunk.len() {
-                        i + step
-                    } else if i < chunk.len() - 1 {
-                        chunk.len() - 1
-                    } else {
-                        continue;
-                    };
-    
-                    // Distribute the results back down the pyramid
-                    let result = func(&chunk[i], &chunk[pair]);
-                    chunk[i] = std::mem::replace(&mut chunk[pair], result);
-                }
-            }).gather().map_err(|_| ScanError::FailedThreadInGather)?;
+            })?;
         }
-    
-        result_vec.extract().ok_or(ScanError::BrokenThreadLocking)
+
+        Ok(())
     }
 
-    pub fn blelloch_scan(&mut self, v: Vec<u64>) -> Result<Vec<u64>, ScanError> {
-        let mut result_vec = split_vector::SplitVector::with_vec(v);
-    
-        // an iterator over the steps up the pyramid (1 2 4 8 ...)
-        let steps = (0..((result_vec.len() as f64).log2().ceil() as usize)).map(|i| 1 << i);
-    
-        /*
-         * First, we build up the pyramid of sections for which we know the total scans
-         */
-        for step in steps.clone() {
-            // split the vector into chunks based on the pyramid ranges for the current step
-            let ranges = pyramid_ranges_for(step, result_vec.len(), self.num_threads(), self.sequential_length);
-            let chunks = result_vec.chunk(&ranges).ok_or(ScanError::InvalidChunking)?.into_iter().map(|i| (step, i)).collect::<Vec<_>>();
-            // distribute the chunks and await results
-            self.thread_pool.sendall(chunks, |_, (step, mut chunk): (usize, split_vector::SplitVectorChunk<u64>)| {
-                /*
-                 * Iterate through the chunks by step * 2, skipping every other element.  should look like
-                 * a  b  c  d  ...
-                 * |  ^  |  ^
-                 * +--+  +--+
-                 * So a -> b, c -> d, instead of a -> b -> c -> d.
-                 * This is what spaces out the pyramids
-                 */
-                for i in (0..chunk.len()).step_by(step * 2) {
-                    let pair = if i + step < chunk.len() {
-                        /* 
-                         * The current position [i] is the peak of the last sub pyramid.  step is the width of the current
-                         * sub pyramid.  [i + step] is the position of the peak of the current sub pyramid, AND the second 
-                         * sub pyramid beneath this one.  Add the two to get the peak for the current pyramid.
-                         */
-                        i + step
-                    } else if i < chunk.len() - 1 {
-                        /*
-                         * If theres less than step amount of extra at the end, round down to the end.
-                         */
-                        chunk.len() - 1
-                    } else {
-                        continue
-                    };
-    
-                    let result = chunk[i] + chunk[pair];
-                    chunk[pair] = result;
-                }
-            }).gather().map_err(|_| ScanError::FailedThreadInGather)?;
+    /**
+     * The second phase of the blelloch scan.  Takes the pyramid built by `blelloch_upsweep`, and turns it into the
+     * exclusive scan of the original vector in place.
+     */
+    pub fn blelloch_downsweep<T: Default + Send + Sync + 'static>(&mut self, data: &mut split_vector::SplitVector<T>, func: fn(&T, &T) -> T) -> Result<(), ScanError> {
+        if data.is_empty() {
+            return Ok(())
         }
-    
+
         /*
-         * Next, convert the pyramid such that each section's peak has the sum of all elements that came before the section.  The topmost peak
-         * should therefore be 0
+         * First, convert the pyramid such that each section's peak has the sum of all elements that came before the section.  The topmost peak
+         * should therefore be the identity
          */
-        let len = result_vec.len();
-        result_vec.view_mut().ok_or(ScanError::BrokenThreadLocking)?[len - 1] = 0;
+        let len = data.len();
+        data.view_mut().ok_or(ScanError::BrokenThreadLocking)?[len - 1] = T::default();
     
         /*
          * Iterate back down the pyramid, and fix each pyramid's peak to be the sum of all previous elements.  Do this by taking the left 
          * sub pyramid's peak, swapping with current peak (same elements came before left pyramid as current pyramid), and set right 
          * sub pyramid's peak to the sum of both.
          */
-        for step in steps.clone().rev() {
-            let ranges = pyramid_ranges_for(step, result_vec.len(), self.num_threads(), self.sequential_length);
-            let chunks = result_vec.chunk(&ranges).ok_or(ScanError::InvalidChunking)?.into_iter().map(|i| (step, i)).collect::<Vec<_>>();
-            self.thread_pool.sendall(chunks, |_, (step, mut chunk): (usize, split_vector::SplitVectorChunk<u64>)| {
+        for step in pyramid_steps(data.len()).rev() {
+            let ranges = pyramid_ranges_for(step, data.len(), self.num_threads(), self.sequential_length);
+            let chunks = data.chunk(&ranges)?.into_iter().map(|i| (step, i, func)).collect::<Vec<_>>();
+            self.map_on_executor(chunks, |_, (step, mut chunk, func): SweepWork<T>| {
                 for i in (0..chunk.len()).step_by(step * 2) {
                     let pair = if i + step < chunk.len() {
                         i + step
@@ -193,20 +134,57 @@ impl Scanner {
                         continue;
                     };
     
-                    // Distribute the results back down the pyramid
-                    let result = chunk[i] + chunk[pair];
+                    // Distribute the results back down the pyramid.  the peak holds everything before this section, and the
+                    // left peak everything in its left half, so the peak goes on the left for non-commutative operators
+                    let result = func(&chunk[pair], &chunk[i]);
                     chunk[i] = std::mem::replace(&mut chunk[pair], result);
                 }
-            }).gather().map_err(|_| ScanError::FailedThreadInGather)?;
+            })?;
         }
-    
+
+        Ok(())
+    }
+
+    pub fn blelloch_scan_generic<T: Default + Send + Sync + 'static>(&mut self, v: Vec<T>, func: fn(&T, &T) -> T) -> Result<Vec<T>, ScanError> {
+        self.set_last_used_simd(false);
+        // this is an exclusive scan, so a single element becomes the identity, and there is no pyramid to build at all
+        if v.len() <= 1 {
+            return Ok(v.into_iter().map(|_| T::default()).collect())
+        }
+
+        let mut result_vec = split_vector::SplitVector::with_vec(v);
+        self.blelloch_upsweep(&mut result_vec, func)?;
+        self.blelloch_downsweep(&mut result_vec, func)?;
         result_vec.extract().ok_or(ScanError::BrokenThreadLocking)
     }
+
+    pub fn blelloch_scan(&mut self, v: Vec<u64>) -> Result<Vec<u64>, ScanError> {
+        self.blelloch_scan_generic(v, |a, b| a + b)
+    }
+
+    /**
+     * Lifts each element into an accumulator, and then scans the accumulators.  This is for scans whose running value
+     * isn't the same type as the elements, such as counting through a vector of flags.  Like `blelloch_scan_generic`,
+     * the scan is exclusive, and the accumulator's default must be the identity of `combine`.
+     */
+    pub fn blelloch_scan_map<E, A>(&mut self, v: Vec<E>, lift: fn(&E) -> A, combine: fn(&A, &A) -> A) -> Result<Vec<A>, ScanError>
+    where A: Default + Send + Sync + 'static {
+        self.blelloch_scan_generic(v.iter().map(lift).collect(), combine)
+    }
+
+    /**
+     * Counts how many elements before each position satisfy the predicate.  Each element is lifted to a 1 or a 0, and
+     * then scanned.  The count is exclusive, so for a kept element it's also the element's index among the kept ones.
+     */
+    pub fn prefix_count<T>(&mut self, data: &[T], pred: fn(&T) -> bool) -> Result<Vec<u64>, ScanError> {
+        self.blelloch_scan(data.iter().map(|x| pred(x) as u64).collect())
+    }
 }
 
 #[cfg(test)]
 mod test {
     use crate::prefix_scans;
+    use crate::util::split_vector;
 
     #[test]
     fn small_test() {
@@ -220,6 +198,97 @@ mod test {
         assert_eq!(baseline.split_last().unwrap().1, &blelloch[1..]);
     }
 
+    #[test]
+    fn lengths_and_threads_test() {
+        for sequential_length in [0, 4, 100] {
+            for num_threads in 1..=8 {
+                let mut scanner = prefix_scans::Scanner::new().with_threads(num_threads).with_sequential_length(sequential_length);
+                for len in 1..=64 {
+                    let list = (1..=len).collect::<Vec<u64>>();
+                    let baseline = prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a + b).unwrap();
+                    let blelloch = scanner.blelloch_scan(list).unwrap();
+                    let case = format!("length {} with {} threads and sequential length {}", len, num_threads, sequential_length);
+                    assert_eq!(blelloch[0], 0, "{}", case);
+                    assert_eq!(blelloch[1..], baseline[..baseline.len() - 1], "{}", case);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn empty_and_single_test() {
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4);
+        assert_eq!(scanner.blelloch_scan(vec![]).unwrap(), Vec::<u64>::new());
+        assert_eq!(scanner.blelloch_scan(vec![42]).unwrap(), vec![0]);
+        assert_eq!(scanner.blelloch_scan_generic(vec![], |a: &u64, b| a + b).unwrap(), Vec::<u64>::new());
+        assert_eq!(scanner.blelloch_scan_generic(vec![42], |a: &u64, b| a + b).unwrap(), vec![0]);
+    }
+
+    #[test]
+    fn upsweep_test() {
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4);
+        for len in [2, 8, 12, 1000] {
+            let mut data = split_vector::SplitVector::with_vec((1..=len).collect::<Vec<u64>>());
+            scanner.blelloch_upsweep(&mut data, |a, b| a + b).unwrap();
+            // the root of the pyramid is the reduction of the whole vector
+            assert_eq!(*data.view_mut().unwrap().last().unwrap(), len * (len + 1) / 2);
+        }
+
+        let mut data = split_vector::SplitVector::with_vec(vec![1, 2, 3, 4]);
+        scanner.blelloch_upsweep(&mut data, |a, b| a + b).unwrap();
+        assert_eq!(data.extract().unwrap(), vec![1, 3, 3, 10]);
+    }
+
+    #[test]
+    fn downsweep_test() {
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4);
+        // the pyramid of [1, 2, 3, 4], as built by the up sweep
+        let mut data = split_vector::SplitVector::with_vec(vec![1u64, 3, 3, 10]);
+        scanner.blelloch_downsweep(&mut data, |a, b| a + b).unwrap();
+        assert_eq!(data.extract().unwrap(), vec![0, 1, 3, 6]);
+
+        let mut data = split_vector::SplitVector::<u64>::with_vec(vec![]);
+        scanner.blelloch_downsweep(&mut data, |a, b| a + b).unwrap();
+    }
+
+    #[test]
+    fn non_commutative_test() {
+        // concatenation is associative but not commutative, so this checks the sums are always combined left to right
+        let words = (0..37).map(|i| ((b'a' + i % 26) as char).to_string()).collect::<Vec<_>>();
+        let expected = (0..words.len()).map(|i| words[..i].concat()).collect::<Vec<_>>();
+        for num_threads in [1, 3, 8] {
+            let mut scanner = prefix_scans::Scanner::new().with_threads(num_threads);
+            assert_eq!(scanner.blelloch_scan_generic(words.clone(), |a, b| a.clone() + b).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn scan_map_test() {
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4);
+        let flags = (0..100).map(|i| i % 3 == 0).collect::<Vec<_>>();
+        let counts = scanner.blelloch_scan_map(flags.clone(), |&flag| flag as u64, |a, b| a + b).unwrap();
+        // the exclusive scan counts the flags before each element
+        for (i, count) in counts.iter().enumerate() {
+            assert_eq!(*count, flags[..i].iter().filter(|&&flag| flag).count() as u64);
+        }
+
+        let bytes = b"some bytes to count".to_vec();
+        let sums = scanner.blelloch_scan_map(bytes.clone(), |&byte| byte as u64, |a, b| a + b).unwrap();
+        assert_eq!(sums.last().unwrap() + *bytes.last().unwrap() as u64, bytes.iter().map(|&byte| byte as u64).sum::<u64>());
+    }
+
+    #[test]
+    fn prefix_count_test() {
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4);
+        assert_eq!(scanner.prefix_count(&[1, 2, 3, 4], |x| x % 2 == 0).unwrap(), vec![0, 0, 1, 1]);
+        assert_eq!(scanner.prefix_count(&[2, 2, 3, 4], |x| x % 2 == 0).unwrap(), vec![0, 1, 2, 2]);
+        assert_eq!(scanner.prefix_count(&[0u64; 0], |x| x % 2 == 0).unwrap(), Vec::<u64>::new());
+
+        let data = (0..1000).collect::<Vec<u64>>();
+        let counts = scanner.prefix_count(&data, |x| x % 3 == 0).unwrap();
+        assert!(counts.iter().enumerate().all(|(i, &count)| count == (i as u64).div_ceil(3)));
+    }
+
     #[test]
     fn medium_500000_test() {
         let list = (0..500000).collect::<Vec<u64>>();
@@ -0,0 +1,79 @@
+use std::time;
+
+use crate::prefix_scans::Scanner;
+
+
+/**
+ * The lengths `calibrate` tries for each setting.
+ */
+const SEQUENTIAL_LENGTHS: [usize; 6] = [1024, 4096, 16384, 65536, 262144, 1048576];
+const CACHE_CHUNK_LENGTHS: [usize; 5] = [32768, 65536, 131072, 262144, 524288];
+/**
+ * Long enough not to fit in the caches, so the cache chunk length makes a difference, and short enough to keep
+ * calibrating quick.
+ */
+const CACHE_CALIBRATION_LENGTH: usize = 1 << 21;
+const RUNS: usize = 3;
+
+/**
+ * The best time out of a few runs of the scan on fresh copies of the data, leaving out the copying.
+ */
+fn time_scan(data: &[u64], mut scan: impl FnMut(Vec<u64>)) -> time::Duration {
+    (0..RUNS).map(|_| {
+        let data = data.to_vec();
+        let start = time::Instant::now();
+        scan(data);
+        start.elapsed()
+    }).min().unwrap()
+}
+
+impl Scanner {
+    /**
+     * Picks `cache_chunk_length` and `sequential_length` for the current machine, by timing scans of synthetic data,
+     * rather than relying on the defaults.  The cache chunk length is the fastest for the divide and conquer scan of a
+     * few megabytes, and the sequential length is the shortest input it scans faster than a single thread does.  If it
+     * never does, the sequential length is set past the longest one tried.  This takes on the order of a hundred
+     * milliseconds, with the scanner's current thread settings, so it's best done once, after setting up the threads.
+     */
+    pub fn calibrate(&mut self) {
+        let data = (0..CACHE_CALIBRATION_LENGTH as u64).collect::<Vec<_>>();
+        self.sequential_length = 0;
+
+        let mut best = None;
+        for cache_chunk_length in CACHE_CHUNK_LENGTHS {
+            self.cache_chunk_length = cache_chunk_length;
+            let elapsed = time_scan(&data, |data| { let _ = self.divide_and_conquer_scan(data); });
+            if best.is_none_or(|(_, best)| elapsed < best) {
+                best = Some((cache_chunk_length, elapsed));
+            }
+        }
+        self.cache_chunk_length = best.unwrap().0;
+
+        self.sequential_length = SEQUENTIAL_LENGTHS.iter().copied().find(|&len| {
+            let data = &data[..len];
+            let sequential = time_scan(data, |mut data| { let _ = self.sequential_scan(&mut data); });
+            let parallel = time_scan(data, |data| { let _ = self.divide_and_conquer_scan(data); });
+            parallel < sequential
+        }).unwrap_or(SEQUENTIAL_LENGTHS[SEQUENTIAL_LENGTHS.len() - 1] * 2);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prefix_scans;
+
+    #[test]
+    fn calibrate_test() {
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4);
+        scanner.calibrate();
+        assert!(super::CACHE_CHUNK_LENGTHS.contains(&scanner.cache_chunk_length));
+        assert!(scanner.sequential_length >= super::SEQUENTIAL_LENGTHS[0]);
+
+        for len in [0, 1, 1000, 100003] {
+            let list = (0..len).collect::<Vec<u64>>();
+            let baseline = prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a + b).unwrap();
+            assert_eq!(scanner.divide_and_conquer_scan(list.clone()).unwrap(), baseline);
+            assert_eq!(scanner.scan(list).unwrap(), baseline);
+        }
+    }
+}
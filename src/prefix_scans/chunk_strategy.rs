@@ -0,0 +1,79 @@
+use std::fmt;
+
+use crate::prefix_scans::helper_functions;
+
+
+/**
+ * Decides where the boundaries between the threads' chunks go.  `ranges` follows the same contract as
+ * `helper_functions::chunk_ranges`: the offsets start at 0, end at `len`, and are strictly ascending, with at most
+ * `num_chunks` chunks between them.  For an empty input, that's just `[0]`.
+ */
+pub trait ChunkStrategy: fmt::Debug + Send + Sync {
+    fn ranges(&self, len: usize, num_chunks: usize) -> Vec<usize>;
+
+    /**
+     * What the offsets are multiples of, apart from the end.  The divide and conquer scan rounds its cache chunks up to
+     * a multiple of this, so that offsets within a cache chunk are aligned in the whole input too.
+     */
+    fn alignment(&self) -> usize {
+        1
+    }
+}
+
+/**
+ * Splits as evenly as possible, with the stragglers going to the first chunks.
+ */
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EvenChunkStrategy;
+
+impl ChunkStrategy for EvenChunkStrategy {
+    fn ranges(&self, len: usize, num_chunks: usize) -> Vec<usize> {
+        helper_functions::chunk_ranges(len, num_chunks)
+    }
+}
+
+/**
+ * Splits as evenly as possible, but rounds each boundary down to a multiple of the alignment, so that chunks start on a
+ * simd vector, cache line or page, depending on the alignment.  Only the end of the last chunk can be unaligned.  Chunks
+ * that round down to nothing are dropped, so short inputs can end up with fewer chunks than asked for.  The scanner
+ * defaults to aligning on the lanes of its detected `SimdWidth`, or of `add_to_all_simd` if that's wider, so that none
+ * of the chunks but the last leave a remainder for the scalar loop, in either sweep.
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct AlignedChunkStrategy(pub usize);
+
+impl ChunkStrategy for AlignedChunkStrategy {
+    fn ranges(&self, len: usize, num_chunks: usize) -> Vec<usize> {
+        let align = std::cmp::max(self.0, 1);
+        let mut ranges = helper_functions::chunk_ranges(len, num_chunks).into_iter()
+            .map(|offset| if offset == len { len } else { offset / align * align })
+            .collect::<Vec<_>>();
+        ranges.dedup();
+        ranges
+    }
+
+    fn alignment(&self) -> usize {
+        std::cmp::max(self.0, 1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prefix_scans::chunk_strategy::{ChunkStrategy, AlignedChunkStrategy, EvenChunkStrategy};
+
+    #[test]
+    fn aligned_chunk_strategy_test() {
+        for (len, num_chunks, align) in [(1000, 4, 8), (1003, 7, 8), (100000, 6, 512), (10, 4, 8), (5, 3, 8), (0, 4, 8)] {
+            let ranges = AlignedChunkStrategy(align).ranges(len, num_chunks);
+            assert_eq!(ranges[0], 0);
+            assert_eq!(*ranges.last().unwrap(), len);
+            assert!(ranges.len() <= num_chunks + 1);
+            assert!(ranges.windows(2).all(|pair| pair[0] < pair[1]));
+            assert!(ranges[..ranges.len() - 1].iter().all(|offset| offset % align == 0));
+        }
+
+        assert_eq!(AlignedChunkStrategy(8).ranges(10, 4), vec![0, 8, 10]);
+        assert_eq!(AlignedChunkStrategy(1).ranges(10, 4), EvenChunkStrategy.ranges(10, 4));
+        assert_eq!((AlignedChunkStrategy(8).alignment(), AlignedChunkStrategy(0).alignment(), EvenChunkStrategy.alignment()), (8, 1, 1));
+    }
+}
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+use std::ops;
+
+use crate::prefix_scans::{Scanner, ScanError};
+use crate::util::split_vector;
+
+
+/**
+ * The work sent to each thread, (output chunk, input, input range, predicate).
+ */
+type CompactWork<T> = (split_vector::SplitVectorChunk<'static, T>, Arc<Vec<T>>, ops::Range<usize>, fn(&T) -> bool);
+
+impl Scanner {
+    /**
+     * Keeps only the elements that satisfy the predicate, in order.  `prefix_count` gives each kept element its index
+     * in the output, so the elements each thread keeps from its chunk of the input land in a contiguous chunk of the
+     * output, starting at the count at the start of its chunk.  Each thread then scatters its kept elements into its own
+     * chunk of the output, without having to wait on any of the others.  Chunks that keep nothing have nothing to scatter
+     * and aren't sent at all.
+     */
+    pub fn compact<T>(&mut self, data: Vec<T>, pred: fn(&T) -> bool) -> Result<Vec<T>, ScanError>
+    where T: Clone + Default + Send + Sync + 'static {
+        let indices = self.prefix_count(&data, pred)?;
+        let len = match (indices.last(), data.last()) {
+            (Some(&index), Some(last)) => index as usize + pred(last) as usize,
+            _ => return Ok(Vec::new())
+        };
+        let index_at = |i: usize| if i == data.len() { len } else { indices[i] as usize };
+
+        let ranges = self.chunk_offsets(data.len())?.windows(2)
+            .filter(|range| index_at(range[0]) < index_at(range[1]))
+            .map(|range| range[0]..range[1]).collect::<Vec<_>>();
+        let offsets = ranges.iter().map(|range| index_at(range.start)).chain(std::iter::once(len)).collect::<Vec<_>>();
+
+        let data = Arc::new(data);
+        let mut output = split_vector::SplitVector::with_size(len);
+        let chunks = output.chunk(&offsets)?.into_iter().zip(ranges)
+            .map(|(chunk, range)| (chunk, data.clone(), range, pred)).collect::<Vec<_>>();
+        self.map_on_executor(chunks, |_, (mut chunk, data, range, pred): CompactWork<T>| {
+            for (slot, x) in chunk.iter_mut().zip(data[range].iter().filter(|x| pred(x))) {
+                *slot = x.clone();
+            }
+        })?;
+
+        output.extract().ok_or(ScanError::BrokenThreadLocking)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prefix_scans;
+
+    #[test]
+    fn compact_test() {
+        for num_threads in [1, 3, 8] {
+            let mut scanner = prefix_scans::Scanner::new().with_threads(num_threads);
+            let evens = scanner.compact((0..100).collect::<Vec<u64>>(), |x| x % 2 == 0).unwrap();
+            assert_eq!(evens, (0..100).step_by(2).collect::<Vec<u64>>());
+
+            // only a few elements are kept, so most of the threads' chunks keep nothing
+            let sparse = scanner.compact((0..1000).collect::<Vec<u64>>(), |&x| x == 3 || x == 998).unwrap();
+            assert_eq!(sparse, vec![3, 998]);
+            assert_eq!(scanner.compact((0..100).collect::<Vec<u64>>(), |_| false).unwrap(), Vec::<u64>::new());
+            assert_eq!(scanner.compact(Vec::<u64>::new(), |_| true).unwrap(), Vec::<u64>::new());
+        }
+
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4);
+        let words = ["keep", "drop", "keep", "this"].iter().map(|word| word.to_string()).collect::<Vec<_>>();
+        assert_eq!(scanner.compact(words, |word| word != "drop").unwrap(), vec!["keep", "keep", "this"]);
+    }
+}
@@ -1,9 +1,149 @@
-use crate::prefix_scans::{Scanner, ScanError};
+use crate::prefix_scans::{Scanner, ScanError, ScanOverflow};
 use crate::prefix_scans::helper_functions;
+use crate::prefix_scans::chunk_strategy::{AlignedChunkStrategy, ChunkStrategy};
 use crate::util::split_vector;
 use crate::util::ranged_vector;
+use std::sync::Arc;
+use std::time;
 
 
+/**
+ * Which kernels the threads should run, sent along with their chunks.  The simd kernels are only used when simd is
+ * enabled and sums are allowed to wrap, otherwise the scalar kernels add according to the overflow mode.  Both kernels
+ * return None if a sum overflowed in `ScanOverflow::Checked` mode.
+ */
+#[derive(Clone, Copy)]
+struct Kernels {
+    simd: bool,
+    simd_width: helper_functions::SimdWidth,
+    overflow: ScanOverflow
+}
+
+impl Kernels {
+    fn uses_simd(self) -> bool {
+        self.simd && self.overflow == ScanOverflow::Wrapping
+    }
+
+    /**
+     * Scans the data, and returns its total, which is 0 if it's empty.
+     */
+    fn prefix_scan(self, data: &mut [u64]) -> Option<u64> {
+        match (self.simd, self.overflow) {
+            (true, ScanOverflow::Wrapping) => return Some(helper_functions::prefix_scan_simd_width(data, 0, self.simd_width)),
+            (false, ScanOverflow::Wrapping) => helper_functions::prefix_scan_no_simd(data),
+            (_, overflow) => helper_functions::prefix_scan_overflowing(data, |a, b| overflow.add(a, b))?
+        }
+
+        Some(data.last().copied().unwrap_or(0))
+    }
+
+    /**
+     * The same as `prefix_scan`, but with `init` carried into the first element, and without the total.
+     */
+    fn prefix_scan_from(self, data: &mut [u64], init: u64) -> Option<()> {
+        match (self.simd, self.overflow) {
+            (true, ScanOverflow::Wrapping) => {
+                helper_functions::prefix_scan_simd_width(data, init, self.simd_width);
+            },
+            (_, overflow) => {
+                if let Some(first) = data.first_mut() {
+                    *first = overflow.add(*first, init)?;
+                }
+                self.prefix_scan(data)?;
+            }
+        }
+
+        Some(())
+    }
+
+    /**
+     * Totals the data without scanning it.
+     */
+    fn reduce(self, data: &[u64]) -> Option<u64> {
+        match (self.simd, self.overflow) {
+            (true, ScanOverflow::Wrapping) => Some(helper_functions::quicksum_simd(data)),
+            (_, overflow) => data.iter().try_fold(0, |acc, x| overflow.add(acc, *x))
+        }
+    }
+
+    fn add_to_all(self, value: u64, data: &mut [u64]) -> Option<()> {
+        match (self.simd, self.overflow) {
+            (true, ScanOverflow::Wrapping) => helper_functions::add_to_all_simd(value, data),
+            (false, ScanOverflow::Wrapping) => helper_functions::add_to_all_no_simd(value, data),
+            (_, overflow) => return helper_functions::add_to_all_overflowing(value, data, |a, b| overflow.add(a, b))
+        }
+
+        Some(())
+    }
+}
+
+/**
+ * The work sent to each thread in either sweep of the generic scan, (chunk, carry, operator).  Nothing is carried into
+ * the first sweep, or into the first chunk.
+ */
+type GenericWork<T> = (split_vector::SplitVectorChunk<'static, T>, Option<T>, fn(&T, &T) -> T);
+
+/**
+ * The work sent to each thread in the second sweep of the tiled generic scan, (chunk, offset of its start, carries,
+ * operator).  The carries span the whole input, and there's nothing to carry into the first chunk of the first tile.
+ */
+type TiledWork<T> = (split_vector::SplitVectorChunk<'static, T>, usize, Arc<ranged_vector::RangedVector<Option<T>>>, fn(&T, &T) -> T);
+
+/**
+ * The part of the chunk, starting at `chunk_start` and `chunk_len` long, that the range covers, relative to the chunk.
+ */
+fn overlap<T>(range: &ranged_vector::Range<T>, chunk_start: usize, chunk_len: usize) -> (usize, usize) {
+    let start = std::cmp::max(range.start(), chunk_start) - chunk_start;
+    let end = std::cmp::min(range.end() - chunk_start, chunk_len);
+    (start, end)
+}
+
+/**
+ * How long each phase of `divide_and_conquer_scan` took, summed over all of the cache chunks.  The first sweep is the
+ * threads scanning their own chunks, the carries are the scan of the chunks' totals, and the second sweep is the threads
+ * adding the carries back onto the chunks.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ScanTimings {
+    pub first_sweep: time::Duration,
+    pub carries: time::Duration,
+    pub second_sweep: time::Duration,
+    pub total: time::Duration
+}
+
+/**
+ * How `divide_and_conquer_scan` will partition an input, from `Scanner::plan`.  If the input is short enough to be
+ * scanned sequentially there are no cache chunks.  Otherwise each cache chunk lists the offsets of the chunks its
+ * threads scan in the first sweep, and of those they add the carries to in the second sweep.  The second sweep skips
+ * the first chunk, which has nothing carried into it.  With parallel tiles, each cache chunk is a single chunk in both
+ * sweeps.  All of the offsets are into the whole input.
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScanPlan {
+    pub sequential: bool,
+    pub cache_chunks: Vec<CacheChunkPlan>
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CacheChunkPlan {
+    pub start: usize,
+    pub end: usize,
+    pub first_sweep: Vec<usize>,
+    pub second_sweep: Vec<usize>
+}
+
+/**
+ * Adds the time since the clock was last read onto the phase, and restarts the clock.  Does nothing if the scan isn't
+ * being timed, so untimed scans never read the clock.
+ */
+fn record(timings: &mut Option<&mut ScanTimings>, clock: &mut Option<time::Instant>, phase: fn(&mut ScanTimings) -> &mut time::Duration) {
+    if let (Some(timings), Some(clock)) = (timings.as_deref_mut(), clock.as_mut()) {
+        let now = time::Instant::now();
+        *phase(timings) += now - *clock;
+        *clock = now;
+    }
+}
+
 impl Scanner {
     /**
      * This algorithm divides the given dataset into `self.num_threads()` chunks.  Each chunk has its prefix sum
@@ -23,62 +163,677 @@ impl Scanner {
      *                           +---------+---------+---------+--------+
      * The threads must be careful to add the right carries to the right portions of their chunk.
      */
-    pub fn divide_and_conquer_scan(&mut self, mut vec: Vec<u64>) -> Result<Vec<u64>, ScanError> {
+    pub fn divide_and_conquer_scan(&mut self, vec: Vec<u64>) -> Result<Vec<u64>, ScanError> {
+        self.scan_with_total(vec).map(|(vec, _)| vec)
+    }
+
+    /**
+     * Scans the input into `out`, replacing whatever was in it.  The scan moves the vector through the `SplitVector`
+     * rather than copying it, so `out`'s allocation is reused, and only grown if it is too small for the input.  This
+     * way scanning repeatedly into the same buffer doesn't allocate.  If the scan fails, `out` is left empty.
+     */
+    pub fn divide_and_conquer_scan_into(&mut self, input: &[u64], out: &mut Vec<u64>) -> Result<(), ScanError> {
+        let mut vec = std::mem::take(out);
+        vec.clear();
+        vec.extend_from_slice(input);
+        *out = self.divide_and_conquer_scan(vec)?;
+        Ok(())
+    }
+
+    /**
+     * Performs the same scan as `divide_and_conquer_scan`, but also returns the grand total.  The total comes for free
+     * from the carries of the last cache chunk, so there is no need for another pass over the data.
+     */
+    pub fn scan_with_total(&mut self, vec: Vec<u64>) -> Result<(Vec<u64>, u64), ScanError> {
+        self.scan_with_total_timed(vec, None)
+    }
+
+    /**
+     * Performs the same scan as `divide_and_conquer_scan`, but also times each of its phases.
+     */
+    pub fn scan_timed(&mut self, vec: Vec<u64>) -> Result<(Vec<u64>, ScanTimings), ScanError> {
+        let mut timings = ScanTimings::default();
+        let start = time::Instant::now();
+        let (vec, _) = self.scan_with_total_timed(vec, Some(&mut timings))?;
+        timings.total = start.elapsed();
+        Ok((vec, timings))
+    }
+
+    /**
+     * The kernels for the scanner's settings, noting whether they're the simd ones for `last_used_simd`.
+     */
+    fn kernels(&self) -> Kernels {
+        let kernels = Kernels { simd: self.simd_enabled(), simd_width: self.simd_width, overflow: self.overflow };
+        self.set_last_used_simd(kernels.uses_simd());
+        kernels
+    }
+
+    /**
+     * How far apart the cache chunks start, which is `cache_chunk_length` rounded up to the chunk strategy's alignment,
+     * so that the chunk strategy's offsets within each cache chunk are aligned in the whole input too.
+     */
+    fn cache_chunk_step(&self) -> usize {
+        self.cache_chunk_length.next_multiple_of(self.chunk_strategy.alignment())
+    }
+
+    /**
+     * Works out how `divide_and_conquer_scan` would partition an input of the given length, without scanning anything.
+     * Fails with `ScanError::InvalidChunking` if the chunk strategy's offsets don't cover the cache chunks, the same as
+     * the scan would.
+     */
+    pub fn plan(&self, len: usize) -> Result<ScanPlan, ScanError> {
+        if len <= 1 || len < self.sequential_length {
+            return Ok(ScanPlan { sequential: true, cache_chunks: vec![] })
+        }
+
+        let step = self.cache_chunk_step();
+        let cache_chunks = (0..len).step_by(step).map(|start| {
+            let end = std::cmp::min(start + step, len);
+            let (first_sweep, second_sweep) = if self.parallel_tiles {
+                (vec![0, end - start], vec![0, end - start])
+            } else {
+                self.sweep_ranges(end - start)?
+            };
+            let offset = |ranges: Vec<usize>| ranges.into_iter().map(|x| x + start).collect();
+            Ok(CacheChunkPlan { start, end, first_sweep: offset(first_sweep), second_sweep: offset(second_sweep) })
+        }).collect::<Result<_, ScanError>>()?;
+
+        Ok(ScanPlan { sequential: false, cache_chunks })
+    }
+
+    /**
+     * The chunk offsets of both sweeps over a cache chunk of the given length, relative to its start.  The first sweep
+     * follows the chunk strategy, and the second evenly splits everything after the first sweep's first chunk, rounded
+     * to the chunk strategy's alignment, so that its carry adds start on a simd block too.  The cache chunk isn't empty,
+     * so once the first sweep's offsets are checked, there's always a first chunk.
+     */
+    fn sweep_ranges(&self, len: usize) -> Result<(Vec<usize>, Vec<usize>), ScanError> {
+        let first_sweep = self.chunk_offsets(len)?;
+        let first_end = first_sweep[1];
+        let remaining_length = len - first_end;
+        let second_sweep = AlignedChunkStrategy(self.chunk_strategy.alignment()).ranges(remaining_length, self.num_chunks(remaining_length))
+            .into_iter().map(|x| x + first_end)
+            .collect();
+        Ok((first_sweep, second_sweep))
+    }
+
+    fn scan_with_total_timed(&mut self, mut vec: Vec<u64>, mut timings: Option<&mut ScanTimings>) -> Result<(Vec<u64>, u64), ScanError> {
+        let mut clock = timings.as_ref().map(|_| time::Instant::now());
+        // pick the kernels to send to the threads, depending on if simd is available and how to handle overflow
+        let kernels = self.kernels();
+
+        if vec.len() <= 1 {
+            let total = vec.first().copied().unwrap_or(0);
+            return Ok((vec, total))
+        }
+
+        // below the sequential length, distributing to the threads costs more than it saves, so don't touch the pool
+        if vec.len() < self.sequential_length {
+            let total = kernels.prefix_scan(&mut vec).ok_or(ScanError::Overflow)?;
+            record(&mut timings, &mut clock, |timings| &mut timings.first_sweep);
+            return Ok((vec, total))
+        }
+
+        if self.parallel_tiles {
+            return self.scan_tiles_timed(vec, kernels, timings, clock)
+        }
+
+        // the running total of all of the cache chunks scanned so far
+        let mut total = 0;
+
         // partition the vector into smaller, more cache friendly sized chunks, to operate on
-        for cache_chunk_start in (0..vec.len()).step_by(self.cache_chunk_length) {
+        let step = self.cache_chunk_step();
+        for cache_chunk_start in (0..vec.len()).step_by(step) {
             // the length of the current cache chunk.  this is either just the size of a cache chunk, or the remaining less-than cache chunk number of elements
-            let current_length = std::cmp::min(self.cache_chunk_length, vec.len() - cache_chunk_start);
+            let current_length = std::cmp::min(step, vec.len() - cache_chunk_start);
+            // carry the total of the previous cache chunks into this one through its first element, so that it's included
+            // in the first thread-chunk's scan, and from there in every carry
+            vec[cache_chunk_start] = self.overflow.add(vec[cache_chunk_start], total).ok_or(ScanError::Overflow)?;
 
             // split up the current cache-chunk into smaller thread-chunks, for each thread to calculate the local prefix scan of independently
-            let chunk_ranges = helper_functions::chunk_ranges(current_length, self.num_threads());
+            let (chunk_ranges, ranges) = self.sweep_ranges(current_length)?;
             let mut data = split_vector::SplitVector::with_vec(vec);
-            let chunks = data.chunk(&chunk_ranges.clone().into_iter().map(|x| x + cache_chunk_start).collect::<Vec<_>>()[..]).unwrap();
+            let chunks = data.chunk(&chunk_ranges.clone().into_iter().map(|x| x + cache_chunk_start).collect::<Vec<_>>()[..])?
+                .into_iter().map(|chunk| (chunk, kernels)).collect::<Vec<_>>();
         
             // receive and accumulate the final sum for each chunk ('carry') to get the real final sums for those ranges
-            let mut totals = self.thread_pool.sendall(chunks, |_, mut chunk| -> u64 { 
-                helper_functions::prefix_scan_simd(chunk.raw_chunk_mut());
-                *chunk.last().unwrap()
-            }).gather().map_err(|_| ScanError::FailedThreadInGather)?;
+            let mut totals = self.map_on_executor(chunks, |_, (mut chunk, kernels)| -> Option<u64> { 
+                kernels.prefix_scan(&mut chunk)
+            })?
+                .into_iter().collect::<Option<Vec<_>>>().ok_or(ScanError::Overflow)?;
+            record(&mut timings, &mut clock, |timings| &mut timings.first_sweep);
 
-            // remove the last element and insert a 0 in the beginning, so that the totals are shifted down.  then prefix sum them
+            // prefix sum the totals.  the last one is then the total up to the end of this cache chunk
+            total = kernels.prefix_scan(&mut totals[..]).ok_or(ScanError::Overflow)?;
+            // remove the last element and insert a 0 in the beginning, so that the totals are shifted down
             totals.pop();
             let mut carries = vec![0];
-            helper_functions::prefix_scan_no_simd(&mut totals[..]);
             carries.append(&mut totals);
 
             // create a ranged vector for storing which carry should be used in which ranges
             let carries = ranged_vector::RangedVector::new(chunk_ranges, carries);
+            record(&mut timings, &mut clock, |timings| &mut timings.carries);
             
-            // on the second sweep, the first chunk has already been calculated, and nothing is carried into it.  distribute the remaining
-            // chunks, combined, over the threads
-            let ranges = helper_functions::chunk_ranges(current_length - carries.get_range(0).unwrap().end(), self.num_threads())
-                .into_iter().map(|x| x + carries.get_range(0).unwrap().end())
-                .collect::<Vec<_>>();
-            // distribute chunks and carries to add to the chunks
-            let chunks = data.chunk(&ranges.clone().into_iter().map(|x| x + cache_chunk_start).collect::<Vec<_>>()).unwrap()
-                .into_iter().enumerate().map(|(i, chunk)| (chunk, ranges[i], carries.clone())).collect::<Vec<_>>();
-            self.thread_pool.sendall(chunks, |_, (mut chunk, chunk_start, carries)| {
-                // these chunks are smaller than the first sweep chunks, so there can be at most two different carry ranges
-                // find which carry's range we are in first
-                let carry_range = carries.get(chunk_start).unwrap();
-                let carry_range_distance = std::cmp::min(chunk.len(), carry_range.end() - chunk_start);
-                helper_functions::add_to_all_simd(*carry_range.value(), &mut chunk[0..carry_range_distance]);
-                if carry_range_distance < chunk.len() {
-                    helper_functions::add_to_all_simd(*carries.next_range(carry_range).unwrap().value(), &mut chunk[carry_range_distance..]);
+            // on the second sweep, the first chunk has already been calculated, and nothing is carried into it.  the remaining
+            // chunks, combined, are split up over the threads, and each is sent along with the carries to add to it
+            let chunks = data.chunk(&ranges.clone().into_iter().map(|x| x + cache_chunk_start).collect::<Vec<_>>())?
+                .into_iter().enumerate().map(|(i, chunk)| (chunk, ranges[i], carries.clone(), kernels)).collect::<Vec<_>>();
+            self.map_on_executor(chunks, |_, (mut chunk, chunk_start, carries, kernels)| -> Option<()> {
+                // with an even split, these chunks are smaller than the first sweep chunks, so they span at most two carry
+                // ranges.  other chunk strategies can leave the first sweep chunks uneven though, so walk through each
+                // carry range the chunk overlaps
+                for carry_range in carries.overlapping(chunk_start, chunk_start + chunk.len()) {
+                    let (start, end) = overlap(carry_range, chunk_start, chunk.len());
+                    kernels.add_to_all(*carry_range.value(), &mut chunk[start..end])?;
                 }
-            }).gather().map_err(|_| ScanError::FailedThreadInGather)?;
+                Some(())
+            })?
+                .into_iter().collect::<Option<()>>().ok_or(ScanError::Overflow)?;
+            record(&mut timings, &mut clock, |timings| &mut timings.second_sweep);
 
             // extract the vector back out of the SplitVector.  fails if a thread failed to release its refcount
             vec = data.extract().ok_or(ScanError::BrokenThreadLocking)?;
         }
 
-        return Ok(vec);
+        Ok((vec, total))
    }
+
+    /**
+     * The parallel tiles version of `scan_with_total_timed`, where each cache chunk is a tile that one thread works on
+     * at a time.  The first sweep totals up each tile, the totals are scanned into each tile's carry in, and the second
+     * sweep scans each tile starting from its carry.  Both sweeps read the whole input, but the second sweep scans each
+     * tile in one go, rather than having to come back to add a carry onto it.
+     */
+    fn scan_tiles_timed(&mut self, vec: Vec<u64>, kernels: Kernels, mut timings: Option<&mut ScanTimings>, mut clock: Option<time::Instant>) -> Result<(Vec<u64>, u64), ScanError> {
+        let mut tile_ranges = (0..vec.len()).step_by(self.cache_chunk_step()).collect::<Vec<_>>();
+        tile_ranges.push(vec.len());
+        let mut data = split_vector::SplitVector::with_vec(vec);
+
+        let tiles = data.chunk(&tile_ranges)?.into_iter().map(|tile| (tile, kernels)).collect::<Vec<_>>();
+        let mut totals = self.map_on_executor(tiles, |_, (tile, kernels)| -> Option<u64> {
+            kernels.reduce(&tile)
+        })?
+            .into_iter().collect::<Option<Vec<_>>>().ok_or(ScanError::Overflow)?;
+        record(&mut timings, &mut clock, |timings| &mut timings.first_sweep);
+
+        // the same as the carries of the thread chunks, shifted down so that nothing is carried into the first tile
+        let total = kernels.prefix_scan(&mut totals[..]).ok_or(ScanError::Overflow)?;
+        totals.pop();
+        let mut carries = vec![0];
+        carries.append(&mut totals);
+        record(&mut timings, &mut clock, |timings| &mut timings.carries);
+
+        let tiles = data.chunk(&tile_ranges)?.into_iter().zip(carries).map(|(tile, carry)| (tile, carry, kernels)).collect::<Vec<_>>();
+        self.map_on_executor(tiles, |_, (mut tile, carry, kernels)| -> Option<()> {
+            kernels.prefix_scan_from(&mut tile, carry)
+        })?
+            .into_iter().collect::<Option<()>>().ok_or(ScanError::Overflow)?;
+        record(&mut timings, &mut clock, |timings| &mut timings.second_sweep);
+
+        let vec = data.extract().ok_or(ScanError::BrokenThreadLocking)?;
+        Ok((vec, total))
+    }
+
+    /**
+     * Scans the chunks as though they were one contiguous vector, in the order given, for data that's spread over
+     * separate buffers.  This is the same as the parallel tiles scan, with the caller's chunks as the tiles.  The chunks
+     * are totalled up in parallel, the totals scanned into each chunk's carry in, and then the chunks are scanned from
+     * their carries in parallel.  The chunks are borrowed, so they're mapped with `map_on_executor_scoped`.
+     */
+    pub fn scan_chunks(&mut self, chunks: &mut [&mut [u64]]) -> Result<(), ScanError> {
+        let kernels = self.kernels();
+
+        let mut carries = self.map_on_executor_scoped(chunks.iter().map(|chunk| &**chunk).collect(), |_, chunk| kernels.reduce(chunk))?
+            .into_iter().collect::<Option<Vec<_>>>().ok_or(ScanError::Overflow)?;
+        kernels.prefix_scan(&mut carries).ok_or(ScanError::Overflow)?;
+        carries.pop();
+        carries.insert(0, 0);
+
+        let work = chunks.iter_mut().map(|chunk| &mut **chunk).zip(carries).collect::<Vec<_>>();
+        self.map_on_executor_scoped(work, |_, (chunk, carry)| kernels.prefix_scan_from(chunk, carry))?
+            .into_iter().collect::<Option<()>>().ok_or(ScanError::Overflow)
+    }
+
+    /**
+     * The first sweep of `divide_and_conquer_scan` on its own, over the whole input rather than a cache chunk at a time:
+     * the offsets of the chunks the chunk strategy splits the data into, and the total of each chunk, summed in parallel
+     * on the executor.  The data is borrowed, so the chunks are mapped with `map_on_executor_scoped`.
+     * There's one more offset than there are totals, with the last being the end of the data.  Scanning the totals gives
+     * each chunk's carry, so a caller can do the second sweep however it likes.  The sums follow the overflow mode, so
+     * `ScanOverflow::Checked` fails with `ScanError::Overflow`.
+     */
+    pub fn block_sums(&mut self, data: &[u64]) -> Result<(Vec<usize>, Vec<u64>), ScanError> {
+        let kernels = self.kernels();
+        let ranges = self.chunk_offsets(data.len())?;
+        let chunks = ranges.windows(2).map(|range| &data[range[0]..range[1]]).collect::<Vec<_>>();
+        let sums = self.map_on_executor_scoped(chunks, |_, chunk| kernels.reduce(chunk))?
+            .into_iter().collect::<Option<Vec<_>>>().ok_or(ScanError::Overflow)?;
+        Ok((ranges, sums))
+    }
+
+    /**
+     * The same two sweeps as `divide_and_conquer_scan`, but over any type with an associative operator, using the scalar
+     * kernels.  There's no cache tiling, and the second sweep reuses the chunks of the first.  The carries are always
+     * combined on the left, so the operator doesn't need to be commutative.
+     */
+    pub fn divide_and_conquer_scan_generic<T>(&mut self, vec: Vec<T>, func: fn(&T, &T) -> T) -> Result<Vec<T>, ScanError>
+    where T: Clone + Default + Send + Sync + 'static {
+        if vec.len() <= 1 {
+            return Ok(vec)
+        }
+
+        let ranges = self.chunk_offsets(vec.len())?;
+        let mut data = split_vector::SplitVector::with_vec(vec);
+        let chunks = data.chunk(&ranges)?.into_iter().map(|chunk| (chunk, None, func)).collect::<Vec<_>>();
+        let totals = self.map_on_executor(chunks, |_, (mut chunk, _, func): GenericWork<T>| -> T {
+            for i in 1..chunk.len() {
+                chunk[i] = func(&chunk[i - 1], &chunk[i]);
+            }
+            chunk.last().unwrap().clone()
+        })?;
+
+        // the carry into each chunk after the first is the reduction of the totals of every chunk before it
+        let mut totals = totals.into_iter();
+        let mut carry = totals.next().unwrap();
+        let mut carries = Vec::with_capacity(totals.len());
+        for total in totals {
+            let next = func(&carry, &total);
+            carries.push(std::mem::replace(&mut carry, next));
+        }
+
+        let chunks = data.chunk(&ranges[1..])?.into_iter().zip(carries)
+            .map(|(chunk, carry)| (chunk, Some(carry), func)).collect::<Vec<_>>();
+        self.map_on_executor(chunks, |_, (mut chunk, carry, func): GenericWork<T>| {
+            if let Some(carry) = carry {
+                for x in chunk.iter_mut() {
+                    *x = func(&carry, x);
+                }
+            }
+        })?;
+
+        data.extract().ok_or(ScanError::BrokenThreadLocking)
+    }
+
+    /**
+     * `divide_and_conquer_scan_generic`, but split into cache chunks like `divide_and_conquer_scan`.  Rather than
+     * scanning the cache chunks one after the other, each sweep goes over all of them at once, so the carries span the
+     * whole input, in a single `RangedVector` mapping each position to what's carried into it.  The first sweep's chunks
+     * follow `plan`.  In the second sweep, every cache chunk after the first is evenly split in full, since its first
+     * chunk has a carry from the cache chunks before it.  There are more chunks than threads, so they queue up on them.
+     */
+    pub fn divide_and_conquer_scan_generic_tiled<T>(&mut self, vec: Vec<T>, func: fn(&T, &T) -> T) -> Result<Vec<T>, ScanError>
+    where T: Clone + Default + Send + Sync + 'static {
+        if vec.len() <= 1 {
+            return Ok(vec)
+        }
+
+        // the offsets of the chunks of every cache chunk in both sweeps, into the whole input
+        let mut first_sweep = vec![0];
+        let mut second_sweep = vec![];
+        let step = self.cache_chunk_step();
+        for start in (0..vec.len()).step_by(step) {
+            let len = std::cmp::min(step, vec.len() - start);
+            let (first, second) = self.sweep_ranges(len)?;
+            let second = if start == 0 { second } else { helper_functions::chunk_ranges(len, self.num_chunks(len)) };
+            first_sweep.extend(first.into_iter().skip(1).map(|x| x + start));
+            second_sweep.extend(second.into_iter().take_while(|x| *x < len).map(|x| x + start));
+        }
+        second_sweep.push(vec.len());
+
+        let mut data = split_vector::SplitVector::with_vec(vec);
+        let chunks = data.chunk(&first_sweep)?.into_iter().map(|chunk| (chunk, None, func)).collect::<Vec<_>>();
+        let totals = self.map_on_executor(chunks, |_, (mut chunk, _, func): GenericWork<T>| -> Option<T> {
+            for i in 1..chunk.len() {
+                chunk[i] = func(&chunk[i - 1], &chunk[i]);
+            }
+            chunk.last().cloned()
+        })?;
+
+        // the carry into each chunk is the reduction of every chunk before it, skipping any empty ones
+        let mut carries = Vec::with_capacity(totals.len());
+        let mut carry: Option<T> = None;
+        for total in totals {
+            let next = match (&carry, total) {
+                (Some(carry), Some(total)) => Some(func(carry, &total)),
+                (None, total) => total,
+                (carry, None) => carry.clone()
+            };
+            carries.push(std::mem::replace(&mut carry, next));
+        }
+        let carries = Arc::new(ranged_vector::RangedVector::new(first_sweep, carries));
+
+        let chunks = data.chunk(&second_sweep)?.into_iter().zip(second_sweep.iter())
+            .map(|(chunk, &start)| (chunk, start, carries.clone(), func)).collect::<Vec<_>>();
+        self.map_on_executor(chunks, |_, (mut chunk, chunk_start, carries, func): TiledWork<T>| {
+            for carry_range in carries.overlapping(chunk_start, chunk_start + chunk.len()) {
+                if let Some(carry) = carry_range.value() {
+                    let (start, end) = overlap(carry_range, chunk_start, chunk.len());
+                    for x in chunk[start..end].iter_mut() {
+                        *x = func(carry, x);
+                    }
+                }
+            }
+        })?;
+
+        data.extract().ok_or(ScanError::BrokenThreadLocking)
+    }
+
+    /**
+     * Sums counters too large for u64, wrapping on overflow of u128.
+     */
+    pub fn divide_and_conquer_scan_u128(&mut self, vec: Vec<u128>) -> Result<Vec<u128>, ScanError> {
+        self.divide_and_conquer_scan_generic(vec, |a, b| a.wrapping_add(*b))
+    }
+
+    /**
+     * Converts each element into a wider type before scanning with addition, so that small integers can be summed
+     * without overflowing, like u32s into u64s.  The conversion is done in one pass up front, and the scan is
+     * `divide_and_conquer_scan_generic`.
+     */
+    pub fn scan_widened<Narrow, Wide>(&mut self, data: Vec<Narrow>) -> Result<Vec<Wide>, ScanError>
+    where Narrow: Into<Wide>, Wide: Clone + Default + Send + Sync + std::ops::Add<Output = Wide> + 'static {
+        self.divide_and_conquer_scan_generic(data.into_iter().map(Into::into).collect(), |a, b| a.clone() + b.clone())
+    }
 }
 
 #[cfg(test)]
 mod test {
     use crate::prefix_scans;
+    use crate::util::split_vector;
+
+    #[test]
+    fn scan_timed_test() {
+        let list = (0..2000000).collect::<Vec<u64>>();
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a + b).unwrap();
+        let (scanned, timings) = prefix_scans::Scanner::new()
+            .with_threads(4)
+            .scan_timed(list)
+            .unwrap();
+        assert_eq!(scanned, baseline);
+
+        // each phase is timed from the end of the one before, so they cover all of the scan but moving the vector out at
+        // the end.  a loaded machine can stretch that out, so the lower bound is generous
+        let phases = timings.first_sweep + timings.carries + timings.second_sweep;
+        assert!(phases <= timings.total);
+        assert!(phases * 4 >= timings.total);
+        assert!(timings.first_sweep > std::time::Duration::ZERO && timings.second_sweep > std::time::Duration::ZERO);
+    }
+
+    #[test]
+    fn chunk_strategy_test() {
+        let list = (0..10003).collect::<Vec<u64>>();
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a + b).unwrap();
+        for align in [1, 8, 300, 4096] {
+            let mut scanner = prefix_scans::Scanner::new()
+                .with_threads(5)
+                .with_cache_chunk_length(5000)
+                .with_chunk_strategy(prefix_scans::chunk_strategy::AlignedChunkStrategy(align));
+            assert_eq!(scanner.divide_and_conquer_scan(list.clone()).unwrap(), baseline);
+        }
+    }
+
+    #[test]
+    fn simd_aligned_chunks_test() {
+        // neither a multiple of 8 nor of the number of threads, so the even split would land mid simd block
+        let list = (0..10007).collect::<Vec<u64>>();
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a + b).unwrap();
+        for simd in [true, false] {
+            let mut scanner = prefix_scans::Scanner::new().with_threads(6).with_cache_chunk_length(4003).with_simd(simd);
+            assert_eq!(scanner.divide_and_conquer_scan(list.clone()).unwrap(), baseline);
+        }
+
+        // by default, every chunk but the last starts on a block of `add_to_all_simd`, in both sweeps
+        let ranges = prefix_scans::Scanner::new().chunk_strategy.ranges(10007, 6);
+        assert_eq!(ranges.len(), 7);
+        assert!(ranges[..6].iter().all(|offset| offset % 8 == 0));
+        let plan = prefix_scans::Scanner::new().with_threads(6).with_cache_chunk_length(4003).plan(10007).unwrap();
+        for cache_chunk in plan.cache_chunks {
+            let mut interior = cache_chunk.first_sweep.iter().chain(cache_chunk.second_sweep.iter()).filter(|&&offset| offset != cache_chunk.end);
+            assert!(interior.all(|offset| offset % 8 == 0));
+        }
+    }
+
+    #[test]
+    fn u128_test() {
+        // each element is close to u64::MAX, so the sums overflow u64 after the second element
+        let list = (0..10003).map(|i| u64::MAX as u128 - i).collect::<Vec<u128>>();
+        let mut baseline = list.clone();
+        for i in 1..baseline.len() {
+            baseline[i] += baseline[i - 1];
+        }
+        assert!(*baseline.last().unwrap() > u64::MAX as u128);
+
+        for num_threads in [1, 3, 8] {
+            let mut scanner = prefix_scans::Scanner::new().with_threads(num_threads);
+            assert_eq!(scanner.divide_and_conquer_scan_u128(list.clone()).unwrap(), baseline);
+
+            let mut sequential = list.clone();
+            scanner.sequential_scan(&mut sequential).unwrap();
+            assert_eq!(sequential, baseline);
+
+            let blelloch = scanner.blelloch_scan_generic(list.clone(), |a, b| a + b).unwrap();
+            assert_eq!(blelloch[1..], baseline[..baseline.len() - 1]);
+        }
+
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4);
+        assert_eq!(scanner.divide_and_conquer_scan_u128(vec![]).unwrap(), Vec::<u128>::new());
+        assert_eq!(scanner.divide_and_conquer_scan_u128(vec![7]).unwrap(), vec![7]);
+        // string concatenation isn't commutative, so this checks the carries are combined on the left
+        let words = (0..20).map(|i| ((b'a' + i) as char).to_string()).collect::<Vec<_>>();
+        let scanned = scanner.divide_and_conquer_scan_generic(words, |a, b| a.clone() + b).unwrap();
+        assert_eq!(scanned.last().unwrap(), "abcdefghijklmnopqrst");
+    }
+
+    #[derive(Debug)]
+    struct SkewedChunkStrategy;
+
+    // puts almost everything in the last chunk, so that the second sweep chunks each span several carry ranges
+    impl prefix_scans::chunk_strategy::ChunkStrategy for SkewedChunkStrategy {
+        fn ranges(&self, len: usize, num_chunks: usize) -> Vec<usize> {
+            let num_chunks = std::cmp::min(num_chunks, len);
+            (0..num_chunks).chain(std::iter::once(len)).collect()
+        }
+    }
+
+    #[test]
+    fn skewed_chunk_strategy_test() {
+        let list = (0..1000).collect::<Vec<u64>>();
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a + b).unwrap();
+        let mut scanner = prefix_scans::Scanner::new().with_threads(8).with_chunk_strategy(SkewedChunkStrategy);
+        assert_eq!(scanner.divide_and_conquer_scan(list.clone()).unwrap(), baseline);
+        assert_eq!(scanner.parallel_reduce_sum(&list).unwrap(), *baseline.last().unwrap());
+
+        // strategies that break the contract fail the scans, rather than panicking or leaving part of the input out
+        let cases: [(BadOffsets, split_vector::ChunkError); 3] = [
+            (|len| vec![0, 5, 5, len], split_vector::ChunkError::NotAscending { at: 2 }),
+            (|len| vec![2, len], split_vector::ChunkError::Incomplete),
+            (|len| vec![0, len - 1], split_vector::ChunkError::Incomplete)
+        ];
+        for (offsets, error) in cases {
+            let failed = |result: Result<(), prefix_scans::ScanError>| matches!(result, Err(prefix_scans::ScanError::InvalidChunking(e)) if e == error);
+            let mut scanner = prefix_scans::Scanner::new().with_threads(4).with_chunk_strategy(BadChunkStrategy(offsets));
+            assert!(failed(scanner.divide_and_conquer_scan(list.clone()).map(|_| ())));
+            assert!(failed(scanner.plan(list.len()).map(|_| ())));
+            assert!(failed(scanner.block_sums(&list).map(|_| ())));
+            assert!(failed(scanner.parallel_reduce_sum(&list).map(|_| ())));
+            assert!(failed(scanner.segmented_scan(std::slice::from_ref(&list)).map(|_| ())));
+            assert!(failed(scanner.scan_argmax(vec![1; 1000]).map(|_| ())));
+            assert!(failed(scanner.compact(list.clone(), |_| true).map(|_| ())));
+            assert!(matches!(scanner.try_scan(list.clone(), |a, b| Ok::<u64, ()>(a + b)),
+                Err(prefix_scans::try_scan::TryScanError::Scan(prefix_scans::ScanError::InvalidChunking(e))) if e == error));
+        }
+    }
+
+    type BadOffsets = fn(usize) -> Vec<usize>;
+
+    /**
+     * Returns whatever offsets its function gives for the length, however many chunks are asked for.
+     */
+    #[derive(Debug)]
+    struct BadChunkStrategy(BadOffsets);
+
+    impl prefix_scans::chunk_strategy::ChunkStrategy for BadChunkStrategy {
+        fn ranges(&self, len: usize, _num_chunks: usize) -> Vec<usize> {
+            (self.0)(len)
+        }
+    }
+
+    #[test]
+    fn plan_test() {
+        let scanner = prefix_scans::Scanner::new().with_threads(4).with_cache_chunk_length(1000)
+            .with_chunk_strategy(prefix_scans::chunk_strategy::AlignedChunkStrategy(8));
+        let plan = scanner.plan(2500).unwrap();
+        assert!(!plan.sequential);
+        let cache_chunk = |start, end, first_sweep: Vec<usize>, second_sweep: Vec<usize>| prefix_scans::divide_and_conquer_scan::CacheChunkPlan {
+            start, end, first_sweep, second_sweep
+        };
+        // both sweeps' offsets are rounded down to the alignment, the second sweep's after splitting the rest evenly
+        assert_eq!(plan.cache_chunks, vec![
+            cache_chunk(0, 1000, vec![0, 248, 496, 744, 1000], vec![248, 432, 624, 808, 1000]),
+            cache_chunk(1000, 2000, vec![1000, 1248, 1496, 1744, 2000], vec![1248, 1432, 1624, 1808, 2000]),
+            cache_chunk(2000, 2500, vec![2000, 2120, 2248, 2368, 2500], vec![2120, 2208, 2304, 2400, 2500])
+        ]);
+
+        // a cache chunk length that isn't a multiple of the alignment is rounded up, so the offsets stay aligned across
+        // the whole input, not just within each cache chunk
+        let plan = scanner.clone().with_cache_chunk_length(1001).plan(2500).unwrap();
+        assert_eq!(plan.cache_chunks.iter().map(|cache_chunk| cache_chunk.start).collect::<Vec<_>>(), vec![0, 1008, 2016]);
+        assert!(plan.cache_chunks.iter().all(|cache_chunk| cache_chunk.first_sweep.iter().all(|&offset| offset % 8 == 0 || offset == cache_chunk.end)));
+
+        assert!(scanner.plan(1).unwrap().sequential);
+        assert!(prefix_scans::Scanner::new().with_sequential_length(100).plan(99).unwrap().sequential);
+    }
+
+    #[test]
+    fn parallel_tiles_test() {
+        for (simd, overflow) in [(true, prefix_scans::ScanOverflow::Wrapping), (false, prefix_scans::ScanOverflow::Wrapping), (true, prefix_scans::ScanOverflow::Checked)] {
+            let mut scanner = prefix_scans::Scanner::new()
+                .with_threads(4)
+                .with_cache_chunk_length(1000)
+                .with_parallel_tiles(true)
+                .with_simd(simd)
+                .with_overflow(overflow);
+            for len in [0, 1, 2, 999, 1000, 1001, 10007] {
+                let list = (0..len).collect::<Vec<u64>>();
+                let baseline = prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a + b).unwrap();
+                let (scanned, total) = scanner.scan_with_total(list).unwrap();
+                assert_eq!(scanned, baseline);
+                assert_eq!(total, baseline.last().copied().unwrap_or(0));
+            }
+        }
+
+        let mut scanner = prefix_scans::Scanner::new()
+            .with_threads(4)
+            .with_cache_chunk_length(1000)
+            .with_parallel_tiles(true)
+            .with_overflow(prefix_scans::ScanOverflow::Checked);
+        let mut list = vec![1; 5000];
+        list[2500] = u64::MAX;
+        assert!(matches!(scanner.divide_and_conquer_scan(list), Err(prefix_scans::ScanError::Overflow)));
+
+        let plan = scanner.plan(2500).unwrap();
+        assert_eq!(plan.cache_chunks[2].first_sweep, vec![2000, 2500]);
+        assert_eq!(plan.cache_chunks[2].second_sweep, vec![2000, 2500]);
+    }
+
+    #[test]
+    fn simd_width_test() {
+        use prefix_scans::helper_functions::SimdWidth;
+        let list = (0..10007).collect::<Vec<u64>>();
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a + b).unwrap();
+        for width in [SimdWidth::Lanes4, SimdWidth::Lanes8, SimdWidth::Lanes16] {
+            let mut scanner = prefix_scans::Scanner::new().with_threads(4).with_cache_chunk_length(1000).with_simd_width(width);
+            assert_eq!(scanner.divide_and_conquer_scan(list.clone()).unwrap(), baseline);
+            assert_eq!(scanner.clone().with_parallel_tiles(true).divide_and_conquer_scan(list.clone()).unwrap(), baseline);
+
+            let mut sequential = list.clone();
+            scanner.sequential_scan(&mut sequential).unwrap();
+            assert_eq!(sequential, baseline);
+        }
+    }
+
+    #[test]
+    fn scan_chunks_test() {
+        let mut a = (0..1000).collect::<Vec<u64>>();
+        let mut b = vec![];
+        let mut c = (1000..1003).collect::<Vec<u64>>();
+        let mut d = (1003..2500).collect::<Vec<u64>>();
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd((0..2500).collect::<Vec<u64>>(), |a, b| a + b).unwrap();
+
+        for num_threads in [1, 2, 8] {
+            let (mut a, mut b, mut c, mut d) = (a.clone(), b.clone(), c.clone(), d.clone());
+            prefix_scans::Scanner::new().with_threads(num_threads).scan_chunks(&mut [&mut a, &mut b, &mut c, &mut d]).unwrap();
+            assert_eq!([a, b, c, d].concat(), baseline);
+        }
+
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4).with_overflow(prefix_scans::ScanOverflow::Checked);
+        scanner.scan_chunks(&mut []).unwrap();
+        c[0] = u64::MAX;
+        assert!(matches!(scanner.scan_chunks(&mut [&mut a, &mut b, &mut c, &mut d]), Err(prefix_scans::ScanError::Overflow)));
+    }
+
+    #[test]
+    fn scan_widened_test() {
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4);
+        let widened: Vec<u64> = scanner.scan_widened(vec![u32::MAX; 4]).unwrap();
+        assert_eq!(widened, (1..=4).map(|i| i * u32::MAX as u64).collect::<Vec<_>>());
+
+        let list = (0..10003).map(|i| i as u16).collect::<Vec<_>>();
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(list.iter().map(|&x| x as u64).collect::<Vec<_>>(), |a, b| a + b).unwrap();
+        assert_eq!(scanner.scan_widened::<u16, u64>(list).unwrap(), baseline);
+    }
+
+    #[test]
+    fn generic_tiled_test() {
+        // composing affine maps, x -> a * x + b, which is associative but not commutative
+        let compose = |f: &(u64, u64), g: &(u64, u64)| (f.0.wrapping_mul(g.0), f.1.wrapping_mul(g.0).wrapping_add(g.1));
+        let list = (0..10007).map(|i| (i % 7 + 1, i)).collect::<Vec<(u64, u64)>>();
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(list.clone(), compose).unwrap();
+
+        for num_threads in [1, 3, 8] {
+            for cache_chunk_length in [1000, 4096, 20000] {
+                let tiled = prefix_scans::Scanner::new()
+                    .with_threads(num_threads)
+                    .with_cache_chunk_length(cache_chunk_length)
+                    .divide_and_conquer_scan_generic_tiled(list.clone(), compose)
+                    .unwrap();
+                assert_eq!(tiled, baseline);
+            }
+        }
+
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4).with_cache_chunk_length(3);
+        for len in 0..20 {
+            let list = (0..len).collect::<Vec<u64>>();
+            let baseline = prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a + b).unwrap();
+            assert_eq!(scanner.divide_and_conquer_scan_generic_tiled(list, |a, b| a + b).unwrap(), baseline);
+        }
+    }
+
+    #[test]
+    fn block_sums_test() {
+        let list = (0..100003).collect::<Vec<u64>>();
+        let total = list.iter().sum::<u64>();
+        for num_threads in [1, 3, 8] {
+            let mut scanner = prefix_scans::Scanner::new().with_threads(num_threads);
+            let (offsets, mut sums) = scanner.block_sums(&list).unwrap();
+            assert_eq!(offsets.len(), sums.len() + 1);
+            assert_eq!((offsets[0], *offsets.last().unwrap()), (0, list.len()));
+            for (range, sum) in offsets.windows(2).zip(sums.iter()) {
+                assert_eq!(list[range[0]..range[1]].iter().sum::<u64>(), *sum);
+            }
+
+            // scanned, the block sums are the totals up to the end of each chunk, the last being the grand total
+            let scanned = scanner.scan(list.clone()).unwrap();
+            prefix_scans::baseline::sequential_scan_simd(&mut sums).unwrap();
+            assert_eq!(*sums.last().unwrap(), total);
+            assert!(offsets[1..].iter().zip(sums.iter()).all(|(&end, &sum)| scanned[end - 1] == sum));
+        }
+
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4);
+        assert_eq!(scanner.block_sums(&[]).unwrap(), (vec![0], vec![]));
+        let mut scanner = scanner.with_overflow(prefix_scans::ScanOverflow::Checked);
+        assert!(matches!(scanner.block_sums(&[u64::MAX, 1]), Err(prefix_scans::ScanError::Overflow)));
+    }
 
     #[test]
     fn small_post_scatter_test() {
@@ -92,4 +847,121 @@ mod test {
             .unwrap();
         assert_eq!(baseline, dac);
     }
+
+    #[test]
+    fn empty_and_single_test() {
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4);
+        assert_eq!(scanner.divide_and_conquer_scan(vec![]).unwrap(), Vec::<u64>::new());
+        assert_eq!(scanner.divide_and_conquer_scan(vec![42]).unwrap(), vec![42]);
+        assert_eq!(scanner.scan_with_total(vec![]).unwrap(), (vec![], 0));
+        assert_eq!(scanner.scan_with_total(vec![42]).unwrap(), (vec![42], 42));
+        assert_eq!(prefix_scans::baseline::sequential_scan_no_simd(vec![], |a: &u64, b| a + b).unwrap(), Vec::<u64>::new());
+        assert_eq!(prefix_scans::baseline::sequential_scan_no_simd(vec![42], |a: &u64, b| a + b).unwrap(), vec![42]);
+    }
+
+    #[test]
+    fn more_threads_than_elements_test() {
+        let list = vec![1, 2, 3];
+        let dac = prefix_scans::Scanner::new()
+            .with_threads(8)
+            .divide_and_conquer_scan(list)
+            .unwrap();
+        assert_eq!(dac, vec![1, 3, 6]);
+    }
+
+    #[test]
+    fn sequential_length_test() {
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4).with_sequential_length(100);
+        for len in [2, 50, 99, 100, 101, 1000] {
+            let list = (0..len).collect::<Vec<u64>>();
+            let baseline = prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a + b).unwrap();
+            assert_eq!(scanner.scan_with_total(list).unwrap(), (baseline.clone(), *baseline.last().unwrap()));
+        }
+
+        // the sequential path still respects the overflow mode
+        scanner.set_overflow(prefix_scans::ScanOverflow::Checked);
+        assert!(scanner.divide_and_conquer_scan(vec![u64::MAX, 1]).is_err());
+    }
+
+    #[test]
+    fn multiple_cache_chunks_test() {
+        let list = (0..1000).collect::<Vec<_>>();
+
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a + b).unwrap();
+        let (dac, total) = prefix_scans::Scanner::new()
+            .with_threads(4)
+            .with_cache_chunk_length(96)
+            .scan_with_total(list)
+            .unwrap();
+        assert_eq!(baseline, dac);
+        assert_eq!(total, 999 * 1000 / 2);
+    }
+
+    #[test]
+    fn scan_into_test() {
+        let list = (0..1000).collect::<Vec<_>>();
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a + b).unwrap();
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4);
+
+        // start with stale data, and more capacity than needed
+        let mut out = vec![7; 2000];
+        let capacity = out.capacity();
+        scanner.divide_and_conquer_scan_into(&list, &mut out).unwrap();
+        assert_eq!(out, baseline);
+        assert_eq!(out.capacity(), capacity);
+
+        scanner.divide_and_conquer_scan_into(&list[..10], &mut out).unwrap();
+        assert_eq!(out, &baseline[..10]);
+        assert_eq!(out.capacity(), capacity);
+    }
+
+    #[test]
+    fn overflow_test() {
+        let mut list = vec![1; 100];
+        list[0] = u64::MAX - 50;
+
+        let wrapped = prefix_scans::Scanner::new()
+            .with_threads(4)
+            .with_overflow(prefix_scans::ScanOverflow::Wrapping)
+            .divide_and_conquer_scan(list.clone())
+            .unwrap();
+        assert_eq!(wrapped, prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a.wrapping_add(*b)).unwrap());
+
+        let saturated = prefix_scans::Scanner::new()
+            .with_threads(4)
+            .with_overflow(prefix_scans::ScanOverflow::Saturating)
+            .divide_and_conquer_scan(list.clone())
+            .unwrap();
+        assert_eq!(saturated, prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a.saturating_add(*b)).unwrap());
+
+        let checked = prefix_scans::Scanner::new()
+            .with_threads(4)
+            .with_overflow(prefix_scans::ScanOverflow::Checked)
+            .divide_and_conquer_scan(list.clone());
+        assert!(matches!(checked, Err(prefix_scans::ScanError::Overflow)));
+
+        // stopping one short of the overflow is fine
+        let checked = prefix_scans::Scanner::new()
+            .with_threads(4)
+            .with_overflow(prefix_scans::ScanOverflow::Checked)
+            .divide_and_conquer_scan(list[..51].to_vec())
+            .unwrap();
+        assert_eq!(*checked.last().unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn scalar_fallback_test() {
+        let list = (0..1000).collect::<Vec<_>>();
+
+        let simd = prefix_scans::Scanner::new()
+            .with_threads(4)
+            .divide_and_conquer_scan(list.clone())
+            .unwrap();
+        let scalar = prefix_scans::Scanner::new()
+            .with_threads(4)
+            .without_simd()
+            .divide_and_conquer_scan(list)
+            .unwrap();
+        assert_eq!(simd, scalar);
+    }
 }
@@ -0,0 +1,96 @@
+use std::io;
+
+use crate::prefix_scans::{Scanner, ScanError};
+use crate::prefix_scans::helper_functions;
+
+
+/**
+ * Reads until the buffer is full, or the reader runs out.  Returns how much of the buffer was filled.
+ */
+fn read_full(reader: &mut impl io::Read, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error)
+        }
+    }
+
+    Ok(filled)
+}
+
+impl Scanner {
+    /**
+     * Scans a stream of little-endian u64s too large to hold in memory, writing the sums out as it goes.  The stream is
+     * read in blocks of `cache_chunk_length` elements, and each block is scanned in parallel with the running total of
+     * the blocks before it carried into its first element, like `IncrementalScanner` does with its batches.  Only one
+     * block is held at a time, and its buffers are reused for every block.  Returns the grand total.  A stream that
+     * ends partway through an element fails with an `UnexpectedEof` error.
+     */
+    pub fn scan_file(&mut self, reader: &mut impl io::Read, writer: &mut impl io::Write) -> Result<u64, ScanError> {
+        let mut bytes = vec![0; self.cache_chunk_length * 8];
+        let mut total = 0;
+
+        loop {
+            let filled = read_full(reader, &mut bytes)?;
+            if filled == 0 {
+                break
+            }
+            if filled % 8 != 0 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the stream ended partway through a u64").into())
+            }
+
+            let mut block = self.take_workspace();
+            helper_functions::read_u64_le_into(&bytes[..filled], &mut block);
+            block[0] = self.overflow.add(block[0], total).ok_or(ScanError::Overflow)?;
+
+            let (block, block_total) = self.scan_with_total(block)?;
+            helper_functions::write_u64_le_into(&block, &mut bytes);
+            self.return_workspace(block);
+            writer.write_all(&bytes[..filled])?;
+            total = block_total;
+
+            if filled < bytes.len() {
+                break
+            }
+        }
+
+        writer.flush()?;
+        Ok(total)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io;
+
+    use crate::prefix_scans;
+
+    #[test]
+    fn scan_file_test() {
+        let data = (0..10007).map(|i| i * 3).collect::<Vec<u64>>();
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(data.clone(), |a, b| a + b).unwrap();
+        let bytes = prefix_scans::helper_functions::write_u64_le(&data);
+
+        // blocks that divide the stream evenly, and ones that don't
+        for block_length in [1, 1000, 10007, 50000] {
+            let mut scanner = prefix_scans::Scanner::new().with_threads(4).with_cache_chunk_length(block_length);
+            let mut output = io::Cursor::new(Vec::new());
+            let total = scanner.scan_file(&mut io::Cursor::new(&bytes), &mut output).unwrap();
+
+            assert_eq!(prefix_scans::helper_functions::read_u64_le(&output.into_inner()), baseline);
+            assert_eq!(total, *baseline.last().unwrap());
+        }
+
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4);
+        let mut output = Vec::new();
+        assert_eq!(scanner.scan_file(&mut io::Cursor::new(Vec::new()), &mut output).unwrap(), 0);
+        assert!(output.is_empty());
+        match scanner.scan_file(&mut io::Cursor::new(&bytes[..12]), &mut output) {
+            Err(prefix_scans::ScanError::Io(error)) => assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof),
+            result => panic!("expected an io error, got {:?}", result)
+        }
+    }
+}
@@ -1,17 +1,95 @@
-use packed_simd;
-use packed_simd::shuffle;
+use std::convert::TryInto;
+
+use crate::util::simd::{self, SimdVector};
 
 
 pub fn prefix_scan_no_simd(data: &mut [u64]) {
     for i in 1..data.len() {
-        data[i] += data[i - 1];
+        data[i] = data[i].wrapping_add(data[i - 1]);
+    }
+}
+
+/**
+ * A scalar prefix scan where each addition is done by `add`, which returns None if it overflows.  The scan stops and
+ * returns None at the first overflow, leaving the data partially scanned.
+ */
+pub fn prefix_scan_overflowing(data: &mut [u64], add: impl Fn(u64, u64) -> Option<u64>) -> Option<()> {
+    for i in 1..data.len() {
+        data[i] = add(data[i - 1], data[i])?;
     }
+
+    Some(())
+}
+
+/**
+ * Whether the cpu supports the vector instructions the simd kernels are written for.  On x86 this is avx2, without
+ * which the wide vectors get split up across narrow registers and end up slower than plain scalar code.  Other
+ * architectures are assumed to have their vector extension.  The standard library caches the detection, so this is cheap.
+ */
+pub fn simd_detected() -> bool {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        is_x86_feature_detected!("avx2")
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    {
+        true
+    }
+}
+
+/**
+ * The length of the leading part of the data that the simd kernels operate on, rounded down to a multiple of the lane
+ * count.  If simd isn't detected this is 0, so that the kernels' scalar loops for the remainder handle everything.
+ */
+#[inline(always)]
+fn simd_length(len: usize, lanes: usize) -> usize {
+    if simd_detected() {
+        (len / lanes) * lanes
+    } else {
+        0
+    }
+}
+
+/**
+ * The simd prefix scan shared by every lane type.  Each chunk of `V::LANES` elements is loaded into a vector, and
+ * added to itself shifted right by 1, 2, 4, ... lanes, with zeros shifted in on the left.  The running total of the
+ * previous chunks is added into the first lane.  See `prefix_scan_simd` for how this plays out on eight lanes.  The
+ * running total is kept up through the elements left over at the end too, so it ends up as the total of the whole scan.
+ * The running total starts out as `init`, so the scan can carry on from where a previous one left off.
+ */
+#[inline(always)]
+fn prefix_scan_lanes<V: SimdVector>(data: &mut [V::Element], init: V::Element) -> V::Element {
+    let mut acc = init;
+    let simd_len = simd_length(data.len(), V::LANES);
+    for i in (0..simd_len).step_by(V::LANES) {
+        /*
+        * Vectorize and add the acc to the next chunk in the form of a simd, so that no memory writes are needed.
+        * The acc can be kept in a register instead, and moved to a simd register for addition, which is a lot faster.
+        */
+        let mut a = V::from_slice(&data[i..]) + V::splat(V::Element::default()).replace(0, acc);
+        let mut distance = 1;
+        while distance < V::LANES {
+            a = a + a.shift_lanes_right(distance);
+            distance <<= 1;
+        }
+
+        acc = a.extract(V::LANES - 1);
+        a.write_to_slice(&mut data[i..]);
+    }
+
+    for x in data[simd_len..].iter_mut() {
+        acc = V::add_elements(acc, *x);
+        *x = acc;
+    }
+
+    acc
 }
 
 /**
  * Performs an in-place prefix scan with addition, using simd operations.  This may not be the best implementation,
  * but it performs fairly well.  Supposing simd can operate on a vector of 8 64-bit numbers at a time, and it wants
  * to add these numbers:
+ * ```text
  *      a     b     c     d     e     f     g     h
  *  +         a     b     c     d     e     f     g
  *  =   a    a+b   b+c   c+d   d+e   e+f   f+g   g+h
@@ -19,91 +97,412 @@ pub fn prefix_scan_no_simd(data: &mut [u64]) {
  *  =   a    a+b  a+..c a+..d  b+..e c+..f d+..g e+..h
  *  +                          a     a+b   a+..c a+..d
  *  =   a    a+b  a+..c a+..d  a+..e a+..f a+..g a+..h
+ * ```
  * So in only three arithmetic operations, eight numbers can be added.  The whole array is chunked by 8 and added this way.
  */
 pub fn prefix_scan_simd(data: &mut [u64]) {
-    let mask_1 = packed_simd::u64x8::new(0, !0, !0, !0, !0, !0, !0, !0);
-    let mask_2 = packed_simd::u64x8::new(0, 0, !0, !0, !0, !0, !0, !0);
-    let mask_3 = packed_simd::u64x8::new(0, 0, 0, 0, !0, !0, !0, !0);
+    prefix_scan_simd_from(data, 0);
+}
 
-    let mut acc = 0;
-    let simd_len = (data.len() / 8) * 8;
-    for i in (0..simd_len).step_by(8) {
-        /*
-        * Vectorize and add the acc to the next chunk in the form of a simd, so that no memory writes are needed.
-        * The acc can be kept in a register instead, and moved to a simd register for addition, which is a lot faster.
-        */
-        let a = packed_simd::u64x8::from_slice_unaligned(&data[i..]) + packed_simd::u64x8::new(acc, 0, 0, 0, 0, 0, 0, 0);
-        let b = (shuffle![a, [7, 0,1,2,3,4,5,6]] as packed_simd::u64x8) & mask_1;
+/**
+ * How many u64 lanes the simd prefix scan works on at a time.  Each doubling takes one more shift and add per vector,
+ * but covers twice the elements with it.  Which is best depends on the width of the cpu's vector registers, so 16 lanes
+ * fill two 512-bit registers, 8 lanes one, and 4 lanes one 256-bit register.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SimdWidth {
+    Lanes4,
+    Lanes8,
+    Lanes16
+}
+
+impl SimdWidth {
+    /**
+     * The width that did best in `simd_width_bench`, for how the crate was built.  The kernels are only compiled for the
+     * target features the crate is built with, whatever the cpu detects at runtime.  `core::simd` keeps wider vectors in
+     * registers, so 16 lanes win once avx512 is compiled in, and otherwise 8.  The plain array lanes are vectorized by
+     * llvm, which does best with 4.
+     */
+    pub fn detect() -> Self {
+        if cfg!(all(feature = "portable_simd", target_feature = "avx512f")) {
+            Self::Lanes16
+        } else if cfg!(feature = "portable_simd") {
+            Self::Lanes8
+        } else {
+            Self::Lanes4
+        }
+    }
 
-        let a = a + b;
-        let b = (shuffle![a, [6,7, 0,1,2,3,4,5]] as packed_simd::u64x8) & mask_2;
+    pub fn lanes(self) -> usize {
+        match self {
+            Self::Lanes4 => simd::u64x4::LANES,
+            Self::Lanes8 => simd::u64x8::LANES,
+            Self::Lanes16 => simd::u64x16::LANES
+        }
+    }
+}
 
-        let a = a + b;
-        let b = (shuffle![a, [4,5,6,7, 0,1,2,3]] as packed_simd::u64x8) & mask_3;
+/**
+ * The general form of `prefix_scan_simd`, `prefix_scan_simd_from` and `prefix_scan_simd_with_total`, over the given
+ * number of lanes.  The shifts are generated from the lane count, so every width scans the same way.
+ */
+pub fn prefix_scan_simd_width(data: &mut [u64], init: u64, width: SimdWidth) -> u64 {
+    match width {
+        SimdWidth::Lanes4 => prefix_scan_lanes::<simd::u64x4>(data, init),
+        SimdWidth::Lanes8 => prefix_scan_lanes::<simd::u64x8>(data, init),
+        SimdWidth::Lanes16 => prefix_scan_lanes::<simd::u64x16>(data, init)
+    }
+}
 
-        let a = a + b;
+/**
+ * The same as `prefix_scan_simd`, but also returns the total, straight from the running total the scan keeps anyway,
+ * rather than reading it back out of the data.  The total of an empty slice is 0.
+ */
+pub fn prefix_scan_simd_with_total(data: &mut [u64]) -> u64 {
+    prefix_scan_lanes::<simd::u64x8>(data, 0)
+}
 
-        acc = a.extract(7);
-        a.write_to_slice_unaligned(&mut data[i..]);
+/**
+ * The same as `prefix_scan_simd`, but seeded with a carry in of `init`, so the first element becomes `init + data[0]`.
+ * This is for continuing a scan from a previous batch, without a second pass to add the carry in.
+ */
+pub fn prefix_scan_simd_from(data: &mut [u64], init: u64) {
+    prefix_scan_lanes::<simd::u64x8>(data, init);
+}
+
+/**
+ * The same three shift prefix scan as `prefix_scan_simd`, but over eight f64 lanes.
+ * Floating point addition is not associative, and the additions inside each simd chunk are done in a tree shape rather
+ * than as a strict left fold, so the result may differ slightly from the sequential baseline due to rounding.
+ */
+pub fn prefix_scan_simd_f64(data: &mut [f64]) {
+    prefix_scan_lanes::<simd::f64x8>(data, Default::default());
+}
+
+/**
+ * 32-bit lanes allow for sixteen numbers per simd vector, which takes a fourth shift to cover:
+ * ```text
+ *      a b c d e f g h i j k l m n o p
+ *  +     a b c d e f g h i j k l m n o     (shifted by 1)
+ *  +       a ..                            (shifted by 2)
+ *  +           a ..                        (shifted by 4)
+ *  +                   a ..                (shifted by 8)
+ * ```
+ */
+pub fn prefix_scan_simd_u32(data: &mut [u32]) {
+    prefix_scan_lanes::<simd::u32x16>(data, Default::default());
+}
+
+/**
+ * The signed equivalent of `prefix_scan_simd_u32`.
+ */
+pub fn prefix_scan_simd_i32(data: &mut [i32]) {
+    prefix_scan_lanes::<simd::i32x16>(data, Default::default());
+}
+
+/**
+ * Element types that have a simd prefix scan kernel.  Lets `Scanner::sequential_scan` pick the right kernel by type,
+ * and fall back to a plain scalar scan when simd is turned off.
+ */
+pub trait SimdScan: Copy + Default + std::ops::Add<Output = Self> {
+    fn prefix_scan_simd(data: &mut [Self]);
+
+    /**
+     * The simd scan over the given number of lanes.  Only u64 has a kernel for each width, the rest have the one.
+     */
+    fn prefix_scan_simd_width(data: &mut [Self], _width: SimdWidth) {
+        Self::prefix_scan_simd(data)
+    }
+
+    /**
+     * Whether the value is anything other than NaN or infinite, which only floats can be.
+     */
+    fn is_finite(self) -> bool {
+        true
     }
 
-    for i in simd_len..data.len() {
-        if i > 0 {
-            data[i] += data[i - 1];
+    /**
+     * Adds two elements the same way the simd kernel's lanes do, so integers wrap on overflow, and floats just add.
+     */
+    fn add_elements(a: Self, b: Self) -> Self;
+
+    fn prefix_scan_no_simd(data: &mut [Self]) {
+        for i in 1..data.len() {
+            data[i] = Self::add_elements(data[i - 1], data[i]);
         }
     }
 }
 
+impl SimdScan for u64 {
+    fn prefix_scan_simd(data: &mut [Self]) {
+        prefix_scan_simd(data)
+    }
+
+    fn add_elements(a: Self, b: Self) -> Self {
+        a.wrapping_add(b)
+    }
+
+    fn prefix_scan_simd_width(data: &mut [Self], width: SimdWidth) {
+        prefix_scan_simd_width(data, 0, width);
+    }
+}
+
+impl SimdScan for f64 {
+    fn prefix_scan_simd(data: &mut [Self]) {
+        prefix_scan_simd_f64(data)
+    }
+
+    fn is_finite(self) -> bool {
+        f64::is_finite(self)
+    }
+
+    fn add_elements(a: Self, b: Self) -> Self {
+        a + b
+    }
+}
+
+impl SimdScan for u32 {
+    fn prefix_scan_simd(data: &mut [Self]) {
+        prefix_scan_simd_u32(data)
+    }
+
+    fn add_elements(a: Self, b: Self) -> Self {
+        a.wrapping_add(b)
+    }
+}
+
+impl SimdScan for i32 {
+    fn prefix_scan_simd(data: &mut [Self]) {
+        prefix_scan_simd_i32(data)
+    }
+
+    fn add_elements(a: Self, b: Self) -> Self {
+        a.wrapping_add(b)
+    }
+}
+
+/**
+ * There are no 128-bit lanes worth using, so u128 always takes the scalar scan, simd or not.
+ */
+impl SimdScan for u128 {
+    fn prefix_scan_simd(data: &mut [Self]) {
+        Self::prefix_scan_no_simd(data)
+    }
+
+    fn add_elements(a: Self, b: Self) -> Self {
+        a.wrapping_add(b)
+    }
+}
+
+/**
+ * Sums up the data by chunks of `V::LANES`, into an accumulation vector, which is summed up at the end.  Where the cpu
+ * has masked loads, the elements left over at the end are loaded as one last vector padded with zeros, so they're added
+ * in one go as well.  Otherwise padding them means copying them one at a time anyway, so they're just added that way.
+ */
+#[inline(always)]
+fn sum_lanes<V: SimdVector>(data: &[V::Element]) -> V::Element {
+    let simd_len = simd_length(data.len(), V::LANES);
+    let mut acc = V::splat(V::Element::default());
+    for i in (0..simd_len).step_by(V::LANES) {
+        acc = acc + V::from_slice(&data[i..]);
+    }
+
+    let tail = &data[simd_len..];
+    if cfg!(all(feature = "portable_simd", target_feature = "avx512f")) {
+        (acc + V::load_or_default(tail)).sum()
+    } else {
+        tail.iter().fold(acc.sum(), |acc, x| V::add_elements(acc, *x))
+    }
+}
+
 /**
  * Quickly sums up the vector by chunks of 8, maintaining an accumulation vector.  Each next 8 int chunk is added to the 
  * accumulation vector, which is then finally summed up, along with "stragglers", or end numbers that didn't fit cleanly
  * into a chunk of 8.
  */
 pub fn quicksum_simd(data: &[u64]) -> u64 {
-    let simd_len = (data.len() / 8) * 8;
-    let mut acc = packed_simd::u64x8::splat(0);
-    for i in (0..simd_len).step_by(8) {
-        let a = packed_simd::u64x8::from_slice_unaligned(&data[i..]); 
-        acc = acc + a;
+    reduce_simd_sum(data)
+}
+
+/**
+ * The f64 equivalent of `quicksum_simd`.  Like `prefix_scan_simd_f64`, the summation order differs from a left fold,
+ * so the result may differ slightly from the sequential sum.
+ */
+pub fn quicksum_simd_f64(data: &[f64]) -> f64 {
+    reduce_simd_sum(data)
+}
+
+/**
+ * Element types that have a simd sum kernel, for `reduce_simd_sum`.  Integers wrap on overflow.
+ */
+pub trait SimdSum: Copy {
+    fn sum_simd(data: &[Self]) -> Self;
+}
+
+impl SimdSum for u64 {
+    fn sum_simd(data: &[Self]) -> Self {
+        sum_lanes::<simd::u64x8>(data)
+    }
+}
+
+impl SimdSum for i64 {
+    fn sum_simd(data: &[Self]) -> Self {
+        sum_lanes::<simd::i64x8>(data)
     }
+}
+
+impl SimdSum for u32 {
+    fn sum_simd(data: &[Self]) -> Self {
+        sum_lanes::<simd::u32x16>(data)
+    }
+}
+
+impl SimdSum for i32 {
+    fn sum_simd(data: &[Self]) -> Self {
+        sum_lanes::<simd::i32x16>(data)
+    }
+}
 
-    acc.wrapping_sum() + (&data[simd_len..data.len()]).iter().sum::<u64>()
+impl SimdSum for f64 {
+    fn sum_simd(data: &[Self]) -> Self {
+        sum_lanes::<simd::f64x8>(data)
+    }
 }
 
+/**
+ * The same as `quicksum_simd`, but over any element type with a simd kernel, picked by the type.
+ */
+pub fn reduce_simd_sum<T: SimdSum>(data: &[T]) -> T {
+    T::sum_simd(data)
+}
+
+/**
+ * How many elements `add_to_all_simd` adds at a time.  Chunks that start on a multiple of this have no scalar adds
+ * except at their ends.
+ */
+pub const ADD_TO_ALL_LANES: usize = simd::u64x8::LANES;
+
 /**
  * Given a value and a dataset, add the value to each element of the dataset.
  */
 pub fn add_to_all_simd(value: u64, data: &mut [u64]) {
     // convert the value into a vector that can be added to the rest of the data chunks
-    let value_vector = packed_simd::u64x8::splat(value);
+    let value_vector = simd::u64x8::splat(value);
     // round the length to the nearest 8
-    let multiple_length = (data.len() / 8) * 8;
-    for i in (0..multiple_length).step_by(8) {
-        unsafe {
-            let mut quad = packed_simd::u64x8::from_slice_unaligned_unchecked(&data[i..]);
-            quad += value_vector;
-            quad.write_to_slice_unaligned_unchecked(&mut data[i..]);
-        }
+    let multiple_length = simd_length(data.len(), ADD_TO_ALL_LANES);
+    for i in (0..multiple_length).step_by(ADD_TO_ALL_LANES) {
+        let quad = simd::u64x8::from_slice(&data[i..]) + value_vector;
+        quad.write_to_slice(&mut data[i..]);
     }
     // fill in the last few elements
-    for i in multiple_length..data.len() {
-        data[i] += value;
+    for x in data[multiple_length..].iter_mut() {
+        *x = x.wrapping_add(value);
+    }
+}
+
+pub fn add_to_all_no_simd(value: u64, data: &mut [u64]) {
+    for x in data.iter_mut() {
+        *x = x.wrapping_add(value);
+    }
+}
+
+/**
+ * Adds the value to each element with `add`, which returns None if it overflows.  Stops and returns None at the first
+ * overflow.
+ */
+pub fn add_to_all_overflowing(value: u64, data: &mut [u64], add: impl Fn(u64, u64) -> Option<u64>) -> Option<()> {
+    for x in data.iter_mut() {
+        *x = add(*x, value)?;
+    }
+
+    Some(())
+}
+
+/**
+ * Appends the little-endian u64s in the bytes to the vector.  Any bytes past the last whole u64 are ignored.  Each
+ * conversion is a fixed size load, so this compiles down to a bulk copy, plus byte swaps on big-endian targets.
+ */
+pub fn read_u64_le_into(bytes: &[u8], out: &mut Vec<u64>) {
+    out.extend(bytes.chunks_exact(8).map(|x| u64::from_le_bytes(x.try_into().unwrap())));
+}
+
+pub fn read_u64_le(bytes: &[u8]) -> Vec<u64> {
+    let mut out = Vec::with_capacity(bytes.len() / 8);
+    read_u64_le_into(bytes, &mut out);
+    out
+}
+
+/**
+ * The big-endian equivalent of `read_u64_le`.
+ */
+pub fn read_u64_be(bytes: &[u8]) -> Vec<u64> {
+    bytes.chunks_exact(8).map(|x| u64::from_be_bytes(x.try_into().unwrap())).collect()
+}
+
+/**
+ * Writes the u64s into the start of the bytes, little-endian.  Panics if the bytes can't hold them all.
+ */
+pub fn write_u64_le_into(data: &[u64], bytes: &mut [u8]) {
+    assert!(bytes.len() >= data.len() * 8);
+    for (x, value) in bytes.chunks_exact_mut(8).zip(data.iter()) {
+        x.copy_from_slice(&value.to_le_bytes());
+    }
+}
+
+pub fn write_u64_le(data: &[u64]) -> Vec<u8> {
+    let mut bytes = vec![0; data.len() * 8];
+    write_u64_le_into(data, &mut bytes);
+    bytes
+}
+
+/**
+ * The big-endian equivalent of `write_u64_le`.
+ */
+pub fn write_u64_be(data: &[u64]) -> Vec<u8> {
+    data.iter().flat_map(|x| x.to_be_bytes()).collect()
+}
+
+/**
+ * Turns an inclusive scan into an exclusive one, by shifting every element right by one, and putting the identity first.
+ */
+pub fn inclusive_to_exclusive(data: &mut [u64], identity: u64) {
+    if let Some(last) = data.len().checked_sub(1) {
+        data.copy_within(0..last, 1);
+        data[0] = identity;
+    }
+}
+
+/**
+ * Turns an exclusive scan into an inclusive one, by adding each of the original elements back onto it.  Wraps on
+ * overflow, the same as the scans.  Panics if the original is a different length.
+ */
+pub fn exclusive_to_inclusive(data: &mut [u64], original: &[u64]) {
+    assert_eq!(data.len(), original.len());
+    for (x, y) in data.iter_mut().zip(original.iter()) {
+        *x = x.wrapping_add(*y);
     }
 }
 
 /**
  * Returns chunks.  For example, dividing 100 into 4 chunks would yield
  * [0, 25, 50, 75, 100]
- * Takes up less space than returning pairs.
+ * Takes up less space than returning pairs.  The result always has num_chunks + 1 offsets, starting at 0 and ending at
+ * len, so it can be passed straight to `SplitVector::chunk`.  If there are fewer elements than chunks, only len chunks
+ * of one element each are made, since empty chunks would not be strictly increasing.
  */
 pub fn chunk_ranges(len: usize, num_chunks: usize) -> Vec<usize> {
+    let num_chunks = std::cmp::min(num_chunks, len);
+    if num_chunks == 0 {
+        return vec![0]
+    }
+
     let chunk_size = len / num_chunks;
     let stragglers = len % num_chunks;
     // if there are any extra elements that dont fit all into one chunk, distribute them amongst the other chunks, from the beginning
     let large_ranges = (0..stragglers).map(|i| i * (chunk_size + 1));
     // the smaller chunks at the end that dont have any stragglers
-    let small_ranges = (stragglers..(num_chunks + 1)).map(|i| (i * chunk_size + stragglers));
+    let small_ranges = (stragglers..(num_chunks + 1)).map(|i| i * chunk_size + stragglers);
     large_ranges.chain(small_ranges).collect()
 }
 
@@ -120,9 +519,132 @@ mod test {
         assert_eq!(prefix_scans::baseline::sequential_scan_no_simd(baseline, |a, b| a + b).unwrap(), list)
     }
 
+    #[test]
+    fn chunk_ranges_test() {
+        assert_eq!(prefix_scans::helper_functions::chunk_ranges(10, 3), vec![0, 4, 7, 10]);
+        assert_eq!(prefix_scans::helper_functions::chunk_ranges(10, 4), vec![0, 3, 6, 8, 10]);
+        assert_eq!(prefix_scans::helper_functions::chunk_ranges(100, 4), vec![0, 25, 50, 75, 100]);
+        assert_eq!(prefix_scans::helper_functions::chunk_ranges(3, 8), vec![0, 1, 2, 3]);
+        assert_eq!(prefix_scans::helper_functions::chunk_ranges(0, 8), vec![0]);
+    }
+
     #[test]
     fn quicksum_test() {
         let vec = (0..35).collect::<Vec<_>>();
-        assert_eq!(prefix_scans::helper_functions::quicksum_simd(&vec), vec.iter().sum());
+        assert_eq!(prefix_scans::helper_functions::quicksum_simd(&vec), vec.iter().sum::<u64>());
+
+        // every length of tail, including none at all
+        for len in 0..=17 {
+            let vec = (1..=len).map(|i| u64::MAX - i).collect::<Vec<_>>();
+            let expected = vec.iter().fold(0, |acc: u64, x| acc.wrapping_add(*x));
+            assert_eq!(prefix_scans::helper_functions::quicksum_simd(&vec), expected);
+        }
+    }
+
+    #[test]
+    fn simd_sequential_f64_test() {
+        let mut list = (0..1003).map(|i| i as f64 * 0.1).collect::<Vec<_>>();
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a + b).unwrap();
+        prefix_scans::helper_functions::prefix_scan_simd_f64(&mut list[..]);
+        for (expected, actual) in baseline.iter().zip(list.iter()) {
+            assert!((expected - actual).abs() <= expected.abs() * 1e-12);
+        }
+    }
+
+    #[test]
+    fn simd_sequential_u32_test() {
+        let mut list = (0..1003).collect::<Vec<u32>>();
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a + b).unwrap();
+        prefix_scans::helper_functions::prefix_scan_simd_u32(&mut list[..]);
+        assert_eq!(baseline, list);
+    }
+
+    #[test]
+    fn simd_sequential_i32_test() {
+        let mut list = (0..1003).map(|i| if i % 3 == 0 { -i } else { i }).collect::<Vec<i32>>();
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a + b).unwrap();
+        prefix_scans::helper_functions::prefix_scan_simd_i32(&mut list[..]);
+        assert_eq!(baseline, list);
+    }
+
+    #[test]
+    fn reduce_simd_sum_test() {
+        // through the crate root, the same path users have
+        for len in [0, 5, 16, 35, 1003] {
+            let unsigned = (0..len).map(|i| i * 7).collect::<Vec<u32>>();
+            assert_eq!(crate::reduce_simd_sum(&unsigned), unsigned.iter().sum::<u32>());
+            let signed = (0..len as i64).map(|i| if i % 2 == 0 { -i } else { i * 3 }).collect::<Vec<i64>>();
+            assert_eq!(crate::reduce_simd_sum(&signed), signed.iter().sum::<i64>());
+        }
+
+        assert_eq!(crate::reduce_simd_sum(&[u32::MAX, 2]), 1);
+        assert_eq!(crate::reduce_simd_sum(&[i64::MIN, -1]), i64::MAX);
+    }
+
+    #[test]
+    fn prefix_scan_simd_with_total_test() {
+        for len in [0, 1, 7, 8, 9, 1003] {
+            let mut data = (1..=len).collect::<Vec<u64>>();
+            let baseline = prefix_scans::baseline::sequential_scan_no_simd(data.clone(), |a, b| a + b).unwrap();
+            let total = prefix_scans::helper_functions::prefix_scan_simd_with_total(&mut data);
+            assert_eq!(data, baseline);
+            assert_eq!(total, len * (len + 1) / 2);
+        }
+    }
+
+    #[test]
+    fn prefix_scan_simd_from_test() {
+        let mut data = vec![1, 2, 3];
+        prefix_scans::helper_functions::prefix_scan_simd_from(&mut data, 10);
+        assert_eq!(data, vec![11, 13, 16]);
+
+        for len in [0, 8, 9, 1003] {
+            let mut data = (1..=len).collect::<Vec<u64>>();
+            let mut baseline = data.clone();
+            baseline.insert(0, 10);
+            let baseline = prefix_scans::baseline::sequential_scan_no_simd(baseline, |a, b| a + b).unwrap();
+            prefix_scans::helper_functions::prefix_scan_simd_from(&mut data, 10);
+            assert_eq!(data, baseline[1..]);
+        }
+    }
+
+    #[test]
+    fn simd_width_test() {
+        use prefix_scans::helper_functions::SimdWidth;
+        for width in [SimdWidth::Lanes4, SimdWidth::Lanes8, SimdWidth::Lanes16] {
+            for len in [0, 1, 3, 4, 15, 16, 17, 1003] {
+                let mut data = (1..=len).collect::<Vec<u64>>();
+                let mut baseline = data.clone();
+                baseline.insert(0, 10);
+                let baseline = prefix_scans::baseline::sequential_scan_no_simd(baseline, |a, b| a + b).unwrap();
+                let total = prefix_scans::helper_functions::prefix_scan_simd_width(&mut data, 10, width);
+                assert_eq!(data, baseline[1..], "{:?}", width);
+                assert_eq!(total, *baseline.last().unwrap());
+            }
+        }
+        assert_eq!(SimdWidth::Lanes16.lanes(), 16);
+    }
+
+    #[test]
+    fn u64_bytes_test() {
+        let data = vec![0, 1, 0x0102030405060708, u64::MAX];
+        let le = prefix_scans::helper_functions::write_u64_le(&data);
+        let be = prefix_scans::helper_functions::write_u64_be(&data);
+        assert_eq!(le[16..24], [8, 7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(be[16..24], [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(prefix_scans::helper_functions::read_u64_le(&le), data);
+        assert_eq!(prefix_scans::helper_functions::read_u64_be(&be), data);
+        assert_ne!(prefix_scans::helper_functions::read_u64_le(&be), data);
+
+        // the bytes past the last whole u64 are ignored
+        assert_eq!(prefix_scans::helper_functions::read_u64_le(&le[..20]), data[..2]);
+        assert_eq!(prefix_scans::helper_functions::read_u64_be(&[]), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn quicksum_f64_test() {
+        let vec = (0..35).map(|i| i as f64 * 0.1).collect::<Vec<_>>();
+        let expected = vec.iter().sum::<f64>();
+        assert!((prefix_scans::helper_functions::quicksum_simd_f64(&vec) - expected).abs() <= expected * 1e-12);
     }
 }
\ No newline at end of file
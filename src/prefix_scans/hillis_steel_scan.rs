@@ -5,21 +5,38 @@ use crate::prefix_scans::helper_functions;
 use crate::util::split_vector;
 
 
+/**
+ * The work sent to each thread for a step, (previous step's data, chunk of the workspace to fill, ranges, step).
+ */
+type StepWork = (Arc<Vec<u64>>, split_vector::SplitVectorChunk<'static, u64>, Arc<Vec<usize>>, usize);
+
 impl Scanner {
     pub fn hillis_steel_scan(&mut self, vec: Vec<u64>) -> Result<Vec<u64>, ScanError> {
+        self.set_last_used_simd(false);
+        // nothing to scan, so don't bother allocating the workspace
+        if vec.len() <= 1 {
+            return Ok(vec)
+        }
+
         // individual step function
-        let do_step = |(index, _), (data, mut chunk, ranges, step): (Arc<Vec<u64>>, split_vector::SplitVectorChunk<u64>, Arc<Vec<usize>>, usize)| {
+        let do_step = |(index, _), (data, mut chunk, ranges, step): StepWork| {
             let start = ranges[index];
             // iterate over the current chunk
-            for i in 0..chunk.len() {
+            for (i, element) in chunk.iter_mut().enumerate() {
                 // performing scan operation, in this case, addition
-                chunk[i] = data[start + i] + data[start + i + step];
+                *element = u64::wrapping_add(data[start + i], data[start + i + step]);
             }
         };
     
-        // allocation
+        /*
+         * The two buffers are allocated once, and ping-pong between being read and written.  Each step reads the previous
+         * step's results out of `data`, and writes its own into `workspace`, then the two swap their vectors.  Whichever
+         * one doesn't hold the result at the end becomes the scanner's workspace for the next call.
+         */
+        let mut workspace = self.take_workspace();
+        workspace.resize(vec.len(), 0);
         let mut data = Arc::new(vec);
-        let mut workspace = split_vector::SplitVector::with_size(data.len());
+        let mut workspace = split_vector::SplitVector::with_vec(workspace);
     
         let mut step = 1;
         while step < data.len() {    
@@ -27,26 +44,20 @@ impl Scanner {
             let ranges = Arc::new(helper_functions::chunk_ranges(operation_count, self.num_threads()));
             // TODO: make sure it doesn't fail when the chunks are not perfectly split up (might have idle threads)
             let split_ranges = ranges.iter().map(|i| *i + step).collect::<Vec<_>>();
-            let chunks = workspace.chunk(&split_ranges).unwrap();
+            let chunks = workspace.chunk(&split_ranges)?;
     
             // broadcast current iteration
             let msgs = chunks.into_iter().map(|chunk| (data.clone(), chunk, ranges.clone(), step)).collect::<Vec<_>>();
-            self.thread_pool.sendall(msgs, do_step).gather().map_err(|_| ScanError::FailedThreadInGather)?;
-            // try where the vectors that are filled are created here, sent with the broadcast to each thread, and then received back
-            // rather than 0 it out, could just fill with garbage, but that would be unsafe
-    
-            let mut result = workspace.extract().unwrap();
-            // this needs to be sped up
-            for i in 0..step {
-                result[i] = data[i];
-            }
-            let tmp = Arc::try_unwrap(data).unwrap();
-            data = Arc::new(result);
-            workspace = split_vector::SplitVector::with_vec(tmp);
+            self.map_on_executor(msgs, do_step)?;
+
+            // the first step elements have no partner this step, so they carry over unchanged
+            workspace.view_mut().ok_or(ScanError::BrokenThreadLocking)?[..step].copy_from_slice(&data[..step]);
+            workspace.swap_vec(Arc::get_mut(&mut data).ok_or(ScanError::BrokenThreadLocking)?).ok_or(ScanError::BrokenThreadLocking)?;
     
             step <<= 1;
         }
     
+        self.return_workspace(workspace.take_vec().ok_or(ScanError::BrokenThreadLocking)?);
         Arc::try_unwrap(data).map_err(|_| ScanError::BrokenThreadLocking)
     }
 }
@@ -67,4 +78,27 @@ mod test {
             .unwrap();
         assert_eq!(baseline, hillis_steel);
     }
+
+    #[test]
+    fn empty_and_single_test() {
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4);
+        assert_eq!(scanner.hillis_steel_scan(vec![]).unwrap(), Vec::<u64>::new());
+        assert_eq!(scanner.hillis_steel_scan(vec![42]).unwrap(), vec![42]);
+    }
+
+    #[test]
+    fn workspace_reuse_test() {
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4);
+        scanner.reserve(1000);
+        let capacity = scanner.workspace.capacity();
+        assert!(capacity >= 1000);
+
+        // the buffers swap roles each step, but both are the same length, so neither scan should allocate a new one
+        for _ in 0..2 {
+            let list = (0..1000).collect::<Vec<u64>>();
+            let baseline = prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a + b).unwrap();
+            assert_eq!(scanner.hillis_steel_scan(list).unwrap(), baseline);
+            assert_eq!(scanner.workspace.capacity(), capacity);
+        }
+    }
 }
\ No newline at end of file
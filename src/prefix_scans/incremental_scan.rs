@@ -0,0 +1,158 @@
+use crate::prefix_scans::{Scanner, ScanError};
+use crate::prefix_scans::segmented_scan::HeadFlagVec;
+
+
+/**
+ * Scans a stream of batches as if they were one long vector, by carrying the running total from each batch into the
+ * next.  Each batch is scanned in parallel with `divide_and_conquer_scan`.
+ */
+pub struct IncrementalScanner {
+    scanner: Scanner,
+    total: u64
+}
+
+impl IncrementalScanner {
+    pub fn new(scanner: Scanner) -> Self {
+        Self { scanner, total: 0 }
+    }
+
+    /**
+     * The sum of every batch scanned so far, which is carried into the next batch.
+     */
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /**
+     * Starts a new stream, so the next batch is scanned from 0.
+     */
+    pub fn reset(&mut self) {
+        self.total = 0
+    }
+
+    /**
+     * Scans the batch in place, continuing on from the previous batches.  The running total is added to the batch's
+     * first element before it's scanned, so it is included in every element of the batch, the same way the cache chunks
+     * of `divide_and_conquer_scan` carry into one another.  The batch is scanned in the scanner's workspace, so repeated
+     * batches of the same size don't allocate.
+     */
+    pub fn scan_batch(&mut self, batch: &mut [u64]) -> Result<(), ScanError> {
+        if batch.is_empty() {
+            return Ok(())
+        }
+
+        let mut vec = self.scanner.take_workspace();
+        vec.extend_from_slice(batch);
+        vec[0] = self.scanner.overflow.add(vec[0], self.total).ok_or(ScanError::Overflow)?;
+
+        let (vec, total) = self.scanner.scan_with_total(vec)?;
+        batch.copy_from_slice(&vec);
+        self.scanner.return_workspace(vec);
+        self.total = total;
+        Ok(())
+    }
+}
+
+/**
+ * Segmented scans a stream of blocks of a flat head flag array as if they were one long `HeadFlagVec`, by carrying the
+ * running sum of the last segment from each block into the next.  Each block is scanned in parallel with
+ * `segmented_scan_flat`, so sums wrap on overflow.
+ */
+pub struct IncrementalSegmentedScanner {
+    scanner: Scanner,
+    carry: u64
+}
+
+impl IncrementalSegmentedScanner {
+    pub fn new(scanner: Scanner) -> Self {
+        Self { scanner, carry: 0 }
+    }
+
+    /**
+     * The running sum of the segment that the last block ended in, which is carried into the next block.
+     */
+    pub fn carry(&self) -> u64 {
+        self.carry
+    }
+
+    /**
+     * Starts a new stream, so the next block is scanned from 0.
+     */
+    pub fn reset(&mut self) {
+        self.carry = 0
+    }
+
+    /**
+     * Segmented scans the block in place, continuing on from the previous blocks.  Unless the block starts with a head
+     * flag, its first element continues the last block's final segment, so the carry is added to it before the block is
+     * scanned.  The scan then carries it along up to the block's first head flag, and no further, the same way the
+     * chunks of `segmented_scan_flat` carry into one another.  Returns `ScanError::InvalidDimensions` if there isn't
+     * exactly one head flag per element.
+     */
+    pub fn scan_block(&mut self, data: &mut [u64], head_flags: &[bool]) -> Result<(), ScanError> {
+        if data.len() != head_flags.len() {
+            return Err(ScanError::InvalidDimensions)
+        }
+        if data.is_empty() {
+            return Ok(())
+        }
+
+        let mut vec = self.scanner.take_workspace();
+        vec.extend_from_slice(data);
+        if !head_flags[0] {
+            vec[0] = vec[0].wrapping_add(self.carry);
+        }
+
+        let vec = HeadFlagVec::new(vec, head_flags.to_vec()).ok_or(ScanError::InvalidDimensions)?;
+        let (vec, _) = self.scanner.segmented_scan_flat(vec)?.into_parts();
+        data.copy_from_slice(&vec);
+        self.carry = *vec.last().unwrap();
+        self.scanner.return_workspace(vec);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prefix_scans;
+
+    #[test]
+    fn two_batches_test() {
+        let mut scanner = prefix_scans::incremental_scan::IncrementalScanner::new(prefix_scans::Scanner::new().with_threads(2));
+        let (mut first, mut second) = ([1, 2, 3], [4, 5, 6]);
+        scanner.scan_batch(&mut first).unwrap();
+        scanner.scan_batch(&mut second).unwrap();
+        assert_eq!([first, second].concat(), vec![1, 3, 6, 10, 15, 21]);
+        assert_eq!(scanner.total(), 21);
+
+        scanner.reset();
+        scanner.scan_batch(&mut [1, 2, 3]).unwrap();
+        assert_eq!(scanner.total(), 6);
+    }
+
+    #[test]
+    fn two_segmented_blocks_test() {
+        let segments = vec![vec![1, 2, 3], vec![4, 5, 6, 7], vec![8], vec![9, 10, 11, 12, 13]];
+        let (data, head_flags) = prefix_scans::segmented_scan::HeadFlagVec::from_vec(&segments).into_parts();
+        let baseline = segments.iter().flat_map(|segment| prefix_scans::baseline::sequential_scan_no_simd(segment.clone(), |a, b| a + b).unwrap()).collect::<Vec<u64>>();
+
+        // split before, at, and just after each head flag, so that blocks start both mid-segment and on a new segment
+        for split in 1..data.len() {
+            let mut scanner = prefix_scans::incremental_scan::IncrementalSegmentedScanner::new(prefix_scans::Scanner::new().with_threads(3));
+            let mut scanned = data.clone();
+            let (first, second) = scanned.split_at_mut(split);
+            scanner.scan_block(first, &head_flags[..split]).unwrap();
+            scanner.scan_block(second, &head_flags[split..]).unwrap();
+            assert_eq!(scanned, baseline, "split at {}", split);
+            assert_eq!(scanner.carry(), 55);
+        }
+
+        let mut scanner = prefix_scans::incremental_scan::IncrementalSegmentedScanner::new(prefix_scans::Scanner::new());
+        assert!(matches!(scanner.scan_block(&mut [1, 2], &[true]), Err(prefix_scans::ScanError::InvalidDimensions)));
+        scanner.scan_block(&mut [1, 2], &[true, false]).unwrap();
+        scanner.reset();
+        let mut block = [1, 2];
+        scanner.scan_block(&mut block, &[false, false]).unwrap();
+        assert_eq!(block, [1, 3]);
+    }
+}
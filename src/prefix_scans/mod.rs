@@ -1,16 +1,121 @@
-use crate::util::thread_pool;
+use crate::util::{executor, thread_pool, split_vector};
 
 pub mod helper_functions;
 pub mod blelloch_scan;
 pub mod hillis_steel_scan;
 pub mod divide_and_conquer_scan;
+pub mod summed_area_table;
+pub mod incremental_scan;
+pub mod segmented_scan;
+pub mod prefix_scan_ext;
+pub mod chunk_strategy;
+pub mod compaction;
+pub mod scan_iter;
+pub mod file_scan;
+pub mod scan_conversion;
+pub mod calibration;
+pub mod argmax_scan;
+pub mod try_scan;
+#[cfg(feature = "ndarray")]
+pub mod ndarray_scan;
+
+pub use prefix_scan_ext::PrefixScanExt;
+pub use scan_iter::{scan_iter, ScanIter};
 
 
 #[derive(Debug)]
 pub enum ScanError {
     BrokenThreadLocking,
     FailedThreadInGather,
-    InvalidChunking
+    InvalidChunking(split_vector::ChunkError),
+    InvalidDimensions,
+    Io(std::io::Error),
+    NonFinite { index: usize },
+    Overflow,
+    VerificationFailed { index: usize }
+}
+
+impl From<split_vector::ChunkError> for ScanError {
+    fn from(error: split_vector::ChunkError) -> Self {
+        Self::InvalidChunking(error)
+    }
+}
+
+impl From<std::io::Error> for ScanError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/**
+ * How the u64 divide and conquer scans handle sums that overflow.  `Wrapping` lets them wrap around, which is what the
+ * simd kernels do anyway.  `Checked` fails the scan with `ScanError::Overflow`, and `Saturating` clamps the sums at
+ * `u64::MAX`.  The simd kernels can't cheaply detect overflow, so both of those force the scalar kernels.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScanOverflow {
+    Wrapping,
+    Checked,
+    Saturating
+}
+
+impl ScanOverflow {
+    /**
+     * Adds the two values according to the mode, returning None if they overflow in `Checked` mode.
+     */
+    pub fn add(self, a: u64, b: u64) -> Option<u64> {
+        match self {
+            Self::Wrapping => Some(a.wrapping_add(b)),
+            Self::Checked => a.checked_add(b),
+            Self::Saturating => Some(a.saturating_add(b))
+        }
+    }
+}
+
+/**
+ * How the float scans handle NaN and infinite values, either of which poisons every prefix sum after it.  `Propagate`
+ * scans them like any other value, `Error` fails the scan with `ScanError::NonFinite` at the first one, and
+ * `SkipAsIdentity` scans them as 0, so each one's prefix sum is just the one before it.  Integer scans ignore this.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NanPolicy {
+    Propagate,
+    Error,
+    SkipAsIdentity
+}
+
+/**
+ * Every setting of a `Scanner` in one place, so that a scanner can be configured all at once, or from a file with the
+ * serde feature.  See the scanner's `with_` functions for what each one does.  The chunk strategy is a trait object,
+ * which can't be written out, so scanners made from a config use the default one, and the rayon backend is left out
+ * so the config doesn't change shape with the rayon feature.  The default is the configuration of `Scanner::new`.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct ScannerConfig {
+    pub num_threads: usize,
+    pub simd_on: bool,
+    pub simd_width: helper_functions::SimdWidth,
+    pub sequential_length: usize,
+    pub cache_chunk_length: usize,
+    pub parallel_tiles: bool,
+    pub parallel_length: usize,
+    pub hillis_steel_length: usize,
+    pub min_chunk_size: usize,
+    pub overflow: ScanOverflow,
+    pub nan_policy: NanPolicy,
+    pub verify: bool,
+    pub thread_affinity: bool,
+    pub work_stealing: bool
+}
+
+impl Default for ScannerConfig {
+    fn default() -> Self {
+        Scanner::new().config()
+    }
 }
 
 pub mod baseline {
@@ -35,9 +140,80 @@ pub mod baseline {
 
 pub struct Scanner {
     simd_on: bool,
+    simd_width: helper_functions::SimdWidth,
     sequential_length: usize,
     cache_chunk_length: usize,
-    thread_pool: thread_pool::ThreadPool
+    parallel_tiles: bool,
+    parallel_length: usize,
+    hillis_steel_length: usize,
+    overflow: ScanOverflow,
+    nan_policy: NanPolicy,
+    verify: bool,
+    workspace: Vec<u64>,
+    thread_affinity: bool,
+    work_stealing: bool,
+    min_chunk_size: usize,
+    chunk_strategy: std::sync::Arc<dyn chunk_strategy::ChunkStrategy>,
+    thread_pool: thread_pool::ThreadPool,
+    executor: Option<std::sync::Arc<std::sync::Mutex<dyn executor::ScanExecutor>>>,
+    last_used_simd: std::sync::atomic::AtomicBool
+}
+
+/**
+ * A clone has the same configuration, but its own thread pool with the same number of threads, and its own workspace.
+ */
+impl Clone for Scanner {
+    fn clone(&self) -> Self {
+        Self {
+            simd_on: self.simd_on,
+            simd_width: self.simd_width,
+            sequential_length: self.sequential_length,
+            cache_chunk_length: self.cache_chunk_length,
+            parallel_tiles: self.parallel_tiles,
+            parallel_length: self.parallel_length,
+            hillis_steel_length: self.hillis_steel_length,
+            overflow: self.overflow,
+            nan_policy: self.nan_policy,
+            verify: self.verify,
+            workspace: Vec::new(),
+            thread_affinity: self.thread_affinity,
+            work_stealing: self.work_stealing,
+            min_chunk_size: self.min_chunk_size,
+            chunk_strategy: self.chunk_strategy.clone(),
+            thread_pool: self.new_pool(self.thread_pool.num_threads()),
+            executor: self.executor.clone(),
+            last_used_simd: std::sync::atomic::AtomicBool::new(self.last_used_simd())
+        }
+    }
+}
+
+impl std::fmt::Debug for Scanner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Scanner")
+            .field("simd_on", &self.simd_on)
+            .field("simd_width", &self.simd_width)
+            .field("sequential_length", &self.sequential_length)
+            .field("cache_chunk_length", &self.cache_chunk_length)
+            .field("parallel_tiles", &self.parallel_tiles)
+            .field("parallel_length", &self.parallel_length)
+            .field("hillis_steel_length", &self.hillis_steel_length)
+            .field("overflow", &self.overflow)
+            .field("nan_policy", &self.nan_policy)
+            .field("verify", &self.verify)
+            .field("thread_affinity", &self.thread_affinity)
+            .field("work_stealing", &self.work_stealing)
+            .field("min_chunk_size", &self.min_chunk_size)
+            .field("chunk_strategy", &self.chunk_strategy)
+            .field("num_threads", &self.num_threads())
+            .field("executor", &self.executor.is_some())
+            .finish()
+    }
+}
+
+impl Default for Scanner {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 /**
@@ -46,7 +222,59 @@ pub struct Scanner {
 impl Scanner {
     pub fn new() -> Self {
         let single_pool = thread_pool::ThreadPool::new(1);
-        Self { simd_on: true, sequential_length: 0, cache_chunk_length: 262144, thread_pool: single_pool }
+        let simd_width = helper_functions::SimdWidth::detect();
+        Self { simd_on: true, simd_width, sequential_length: 0, cache_chunk_length: 262144, parallel_tiles: false, parallel_length: 65536, hillis_steel_length: 0, overflow: ScanOverflow::Wrapping, nan_policy: NanPolicy::Propagate, verify: false, workspace: Vec::new(), thread_affinity: false, work_stealing: false,
+            min_chunk_size: 1, chunk_strategy: std::sync::Arc::new(chunk_strategy::AlignedChunkStrategy(std::cmp::max(simd_width.lanes(), helper_functions::ADD_TO_ALL_LANES))), thread_pool: single_pool, executor: None,
+            last_used_simd: std::sync::atomic::AtomicBool::new(false) }
+    }
+
+    /**
+     * A scanner whose results are bit for bit the same as the sequential baseline, for reproducing float results.  It
+     * has a single thread, so `scan` never splits the data, and never touches the pool, and simd is off, since the simd
+     * float kernels add within each vector in a tree shape, which rounds differently from a left fold.
+     */
+    pub fn sequential() -> Self {
+        Self::new().with_threads(1).without_simd()
+    }
+
+    pub fn from_config(config: ScannerConfig) -> Self {
+        Self::new()
+            .with_thread_affinity(config.thread_affinity)
+            .with_work_stealing(config.work_stealing)
+            .with_threads(config.num_threads)
+            .with_simd(config.simd_on)
+            .with_simd_width(config.simd_width)
+            .with_sequential_length(config.sequential_length)
+            .with_cache_chunk_length(config.cache_chunk_length)
+            .with_parallel_tiles(config.parallel_tiles)
+            .with_parallel_length(config.parallel_length)
+            .with_hillis_steel_length(config.hillis_steel_length)
+            .with_min_chunk_size(config.min_chunk_size)
+            .with_overflow(config.overflow)
+            .with_nan_policy(config.nan_policy)
+            .with_verify(config.verify)
+    }
+
+    /**
+     * The scanner's current settings, which `from_config` turns back into an equivalent scanner.
+     */
+    pub fn config(&self) -> ScannerConfig {
+        ScannerConfig {
+            num_threads: self.num_threads(),
+            simd_on: self.simd_on,
+            simd_width: self.simd_width,
+            sequential_length: self.sequential_length,
+            cache_chunk_length: self.cache_chunk_length,
+            parallel_tiles: self.parallel_tiles,
+            parallel_length: self.parallel_length,
+            hillis_steel_length: self.hillis_steel_length,
+            min_chunk_size: self.min_chunk_size,
+            overflow: self.overflow,
+            nan_policy: self.nan_policy,
+            verify: self.verify,
+            thread_affinity: self.thread_affinity,
+            work_stealing: self.work_stealing
+        }
     }
 
     pub fn without_simd(mut self) -> Self {
@@ -54,23 +282,286 @@ impl Scanner {
         self
     }
 
+    pub fn with_simd(mut self, simd_on: bool) -> Self {
+        self.simd_on = simd_on;
+        self
+    }
+
+    /**
+     * How many lanes the u64 simd prefix scans work on at a time.  Defaults to `SimdWidth::detect`.  The other element
+     * types, and the rest of the simd kernels, have a fixed width.
+     */
+    pub fn with_simd_width(mut self, simd_width: helper_functions::SimdWidth) -> Self {
+        self.simd_width = simd_width;
+        self
+    }
+
+    /**
+     * The scans need at least the calling thread, so 0 threads is clamped to 1.
+     */
     pub fn with_threads(mut self, num_threads: usize) -> Self {
-        self.thread_pool = thread_pool::ThreadPool::new(num_threads);
+        self.set_threads(num_threads);
+        self
+    }
+
+    /**
+     * Pins each of the pool's worker threads to its own core, so they don't migrate between cores or NUMA nodes in the
+     * middle of a scan.  This is best-effort, and does nothing on platforms that don't support it.  The pool is rebuilt
+     * with the same number of threads.
+     */
+    pub fn with_thread_affinity(mut self, thread_affinity: bool) -> Self {
+        self.thread_affinity = thread_affinity;
+        self.thread_pool = self.new_pool(self.thread_pool.num_threads());
         self
     }
 
+    /**
+     * Has the pool's threads pull their work from a shared queue, rather than each being handed a fixed chunk.  This
+     * helps when chunks take uneven amounts of time, at the cost of some contention on the queue.  Off by default.
+     */
+    pub fn with_work_stealing(mut self, work_stealing: bool) -> Self {
+        self.work_stealing = work_stealing;
+        self.thread_pool.set_work_stealing(work_stealing);
+        self
+    }
+
+    /**
+     * Runs the scans' work on a rayon pool with the same number of threads, rather than on the scanner's own threads.
+     * The algorithms are the same either way.  Setting the number of threads afterwards keeps the scanner on rayon.
+     */
+    #[cfg(feature = "rayon")]
+    pub fn with_rayon(mut self) -> Self {
+        self.thread_pool.set_rayon(true);
+        self
+    }
+
+    /**
+     * Runs the scans' work on the given executor, rather than on the scanner's own pool, so that the scanner has no
+     * threads of its own.  Clones of the scanner share the executor, taking turns with it, one map at a time.  The
+     * scanner's thread settings apply only to its own pool, which is left with the one thread it has to begin with.
+     */
+    pub fn with_executor(mut self, executor: impl executor::ScanExecutor + 'static) -> Self {
+        self.set_executor(executor);
+        self
+    }
+
+    pub fn set_executor(&mut self, executor: impl executor::ScanExecutor + 'static) {
+        self.executor = Some(std::sync::Arc::new(std::sync::Mutex::new(executor)));
+    }
+
+    /**
+     * Maps the messages on the executor if there is one, and otherwise on the scanner's pool.
+     */
+    fn map_on_executor<S: 'static + Send, R: 'static + Send>(&mut self, msgs: Vec<S>, function: fn(thread_pool::ThreadId, S) -> R) -> Result<Vec<R>, ScanError> {
+        match &self.executor {
+            Some(executor) => executor.lock().map_err(|_| ScanError::BrokenThreadLocking)?.map(msgs, function),
+            None => self.thread_pool.map(msgs, function)
+        }
+    }
+
+    /**
+     * The same as `map_on_executor`, but for messages and functions that borrow from the caller, with `map_scoped`.
+     */
+    fn map_on_executor_scoped<'env, S: Send + 'env, R: Send + 'env>(&mut self, msgs: Vec<S>, function: impl Fn(thread_pool::ThreadId, S) -> R + Sync + 'env) -> Result<Vec<R>, ScanError> {
+        match &self.executor {
+            Some(executor) => executor.lock().map_err(|_| ScanError::BrokenThreadLocking)?.map_scoped(msgs, function),
+            None => (&mut self.thread_pool as &mut dyn executor::ScanExecutor).map_scoped(msgs, function)
+        }
+    }
+
+    /**
+     * Creates a pool with the scanner's thread settings.
+     */
+    fn new_pool(&self, num_threads: usize) -> thread_pool::ThreadPool {
+        let mut pool = thread_pool::ThreadPool::with_affinity(num_threads, self.thread_affinity);
+        pool.set_work_stealing(self.work_stealing);
+        #[cfg(feature = "rayon")]
+        pool.set_rayon(self.thread_pool.rayon());
+        pool
+    }
+
+    /**
+     * Below this length, the work isn't worth distributing to the threads.  The divide and conquer scan scans shorter
+     * inputs sequentially, and the blelloch scan does any step with fewer operations than this on a single thread.
+     */
     pub fn with_sequential_length(mut self, sequential_length: usize) -> Self {
         self.sequential_length = sequential_length;
         self
     }
 
+    /**
+     * The divide and conquer scan steps through the input a cache chunk at a time, so a length of 0 is clamped to 1.  It
+     * also rounds the length up to a multiple of the chunk strategy's alignment, so the chunks stay aligned.
+     */
     pub fn with_cache_chunk_length(mut self, cache_chunk_length: usize) -> Self {
-        self.cache_chunk_length = cache_chunk_length;
+        self.set_cache_chunk_length(cache_chunk_length);
+        self
+    }
+
+    /**
+     * Has the divide and conquer scan hand out whole cache chunks to the threads, rather than splitting up each cache
+     * chunk in turn.  This takes an extra read of the input, to total up every cache chunk before any are scanned, but
+     * there's only the one round trip through the pool for each sweep, rather than per cache chunk.  An input with
+     * fewer cache chunks than threads leaves some of the threads idle.  Off by default.
+     */
+    pub fn with_parallel_tiles(mut self, parallel_tiles: bool) -> Self {
+        self.parallel_tiles = parallel_tiles;
+        self
+    }
+
+    /**
+     * Inputs shorter than this are scanned sequentially by `scan`, since the overhead of distributing them to threads
+     * outweighs the work.
+     */
+    pub fn with_parallel_length(mut self, parallel_length: usize) -> Self {
+        self.parallel_length = parallel_length;
+        self
+    }
+
+    /**
+     * Inputs at least `parallel_length` long but shorter than this are scanned with `hillis_steel_scan` by `scan`.  By
+     * default this is 0, so the divide and conquer scan is always used instead, as it did the least work in the benches.
+     */
+    pub fn with_hillis_steel_length(mut self, hillis_steel_length: usize) -> Self {
+        self.hillis_steel_length = hillis_steel_length;
+        self
+    }
+
+    /**
+     * The smallest chunk worth giving a thread.  Inputs too short to give every thread a chunk this big are split
+     * between fewer threads instead, so medium inputs aren't spread so thin that handing out the chunks costs more than
+     * scanning them.  0 is clamped to 1, which always uses every thread.
+     */
+    pub fn with_min_chunk_size(mut self, min_chunk_size: usize) -> Self {
+        self.set_min_chunk_size(min_chunk_size);
+        self
+    }
+
+    /**
+     * Where the boundaries go when the input is split into a chunk per thread.
+     */
+    pub fn with_chunk_strategy(mut self, chunk_strategy: impl chunk_strategy::ChunkStrategy + 'static) -> Self {
+        self.set_chunk_strategy(chunk_strategy);
+        self
+    }
+
+    pub fn with_overflow(mut self, overflow: ScanOverflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /**
+     * See `NanPolicy`.  Defaults to `Propagate`, which costs nothing, where the other two take an extra pass over the
+     * data before scanning it.
+     */
+    pub fn with_nan_policy(mut self, nan_policy: NanPolicy) -> Self {
+        self.nan_policy = nan_policy;
         self
     }
 
+    /**
+     * A debugging mode, where `scan` keeps a copy of its input, and checks its output against the sequential baseline,
+     * failing with `ScanError::VerificationFailed` at the first element that differs.  This doubles the memory and
+     * more than doubles the time of a scan, so it's off by default, and only compiled in with debug assertions.
+     */
+    pub fn with_verify(mut self, verify: bool) -> Self {
+        self.verify = verify;
+        self
+    }
+
+    /**
+     * Grows the workspace to hold at least `len` elements, so that the scans which need scratch space don't have to
+     * allocate it on their first call.
+     */
+    pub fn reserve(&mut self, len: usize) {
+        self.workspace.clear();
+        self.workspace.reserve(len);
+    }
+
+    /**
+     * Takes the workspace out of the scanner, cleared, for a scan to use as scratch space.  It should be handed back with
+     * `return_workspace` once the scan is done, so that the next scan reuses its allocation.
+     */
+    fn take_workspace(&mut self) -> Vec<u64> {
+        let mut workspace = std::mem::take(&mut self.workspace);
+        workspace.clear();
+        workspace
+    }
+
+    fn return_workspace(&mut self, workspace: Vec<u64>) {
+        self.workspace = workspace;
+    }
+
+    /**
+     * The executor's number of threads if it has one, and otherwise the pool's.
+     */
     pub fn num_threads(&self) -> usize {
-        self.thread_pool.num_threads()
+        match &self.executor {
+            Some(executor) => executor.lock().map_or(1, |executor| executor.num_threads()),
+            None => self.thread_pool.num_threads()
+        }
+    }
+
+    /**
+     * How many chunks to split an input of this length into, one per thread, but without any smaller than
+     * `min_chunk_size`.
+     */
+    fn num_chunks(&self, len: usize) -> usize {
+        std::cmp::max(std::cmp::min(self.num_threads(), len / self.min_chunk_size), 1)
+    }
+
+    /**
+     * The chunk strategy's offsets for an input of this length, checked against the `ChunkStrategy` contract, since a
+     * strategy that leaves part of the input out of every chunk would otherwise scan without it, and silently return the
+     * wrong results.  Fails with `ScanError::InvalidChunking` unless the offsets ascend strictly from 0 to `len`.
+     */
+    fn chunk_offsets(&self, len: usize) -> Result<Vec<usize>, ScanError> {
+        let offsets = self.chunk_strategy.ranges(len, self.num_chunks(len));
+        split_vector::validate_offsets(&offsets, len)?;
+        if offsets.first() != Some(&0) || offsets.last() != Some(&len) {
+            return Err(ScanError::InvalidChunking(split_vector::ChunkError::Incomplete))
+        }
+        Ok(offsets)
+    }
+
+    /**
+     * Whether the last u64 scan ran the simd kernels, or the scalar ones, which also covers scans that only have scalar
+     * kernels, like the blelloch and hillis steel scans.  False until the first scan.  Unlike `simd_enabled`, this also
+     * reflects the overflow mode, since anything but wrapping forces the scalar kernels.
+     */
+    pub fn last_used_simd(&self) -> bool {
+        self.last_used_simd.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    fn set_last_used_simd(&self, used_simd: bool) {
+        self.last_used_simd.store(used_simd, std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /**
+     * Whether the scans will use the simd kernels.  This is only the case if simd hasn't been turned off, and the cpu
+     * actually supports it.
+     */
+    pub fn simd_enabled(&self) -> bool {
+        self.simd_on && helper_functions::simd_detected()
+    }
+
+    /**
+     * Changes the number of threads, keeping the rest of the scanner as it is.  The pool is only rebuilt if the number
+     * actually changes.
+     */
+    pub fn set_threads(&mut self, num_threads: usize) {
+        let num_threads = std::cmp::max(num_threads, 1);
+        if num_threads != self.thread_pool.num_threads() {
+            self.thread_pool = self.new_pool(num_threads);
+        }
+    }
+
+    pub fn set_simd(&mut self, simd_on: bool) {
+        self.simd_on = simd_on
+    }
+
+    pub fn set_simd_width(&mut self, simd_width: helper_functions::SimdWidth) {
+        self.simd_width = simd_width
     }
 
     pub fn set_sequential_length(&mut self, sequential_length: usize) {
@@ -78,21 +569,221 @@ impl Scanner {
     }
 
     pub fn set_cache_chunk_length(&mut self, cache_chunk_length: usize) {
-        self.cache_chunk_length = cache_chunk_length
+        self.cache_chunk_length = std::cmp::max(cache_chunk_length, 1)
+    }
+
+    pub fn set_parallel_tiles(&mut self, parallel_tiles: bool) {
+        self.parallel_tiles = parallel_tiles
+    }
+
+    pub fn set_parallel_length(&mut self, parallel_length: usize) {
+        self.parallel_length = parallel_length
+    }
+
+    pub fn set_hillis_steel_length(&mut self, hillis_steel_length: usize) {
+        self.hillis_steel_length = hillis_steel_length
+    }
+
+    pub fn set_min_chunk_size(&mut self, min_chunk_size: usize) {
+        self.min_chunk_size = std::cmp::max(min_chunk_size, 1)
+    }
+
+    pub fn set_chunk_strategy(&mut self, chunk_strategy: impl chunk_strategy::ChunkStrategy + 'static) {
+        self.chunk_strategy = std::sync::Arc::new(chunk_strategy)
+    }
+
+    pub fn set_overflow(&mut self, overflow: ScanOverflow) {
+        self.overflow = overflow
+    }
+
+    pub fn set_nan_policy(&mut self, nan_policy: NanPolicy) {
+        self.nan_policy = nan_policy
+    }
+
+    pub fn set_verify(&mut self, verify: bool) {
+        self.verify = verify
+    }
+
+    /**
+     * Splits the data into a chunk per thread, and maps each chunk on the executor with `map_on_executor_scoped`,
+     * returning the results in chunk order.  A chunk that panics fails the map with `ScanError::FailedThreadInGather`,
+     * like the pool's maps.
+     */
+    fn map_chunks_scoped<T: Sync, R: Send>(&mut self, data: &[T], func: impl Fn(&[T]) -> R + Sync) -> Result<Vec<R>, ScanError> {
+        let ranges = self.chunk_offsets(data.len())?;
+        let chunks = ranges.windows(2).map(|range| &data[range[0]..range[1]]).collect::<Vec<_>>();
+        self.map_on_executor_scoped(chunks, |_, chunk| func(chunk))
+    }
+
+    pub fn parallel_quicksum_simd(&mut self, data: &[u64]) -> Result<u64, ScanError> {
+        Ok(self.map_chunks_scoped(data, helper_functions::quicksum_simd)?.into_iter().sum())
     }
 
-    pub fn parallel_quicksum_simd(&mut self, data: &[u64]) -> u64 {
-        let ranges = helper_functions::chunk_ranges(data.len(), self.num_threads());
+    /**
+     * The grand total of the data, without scanning it.  This is just the first sweep of `divide_and_conquer_scan`,
+     * stopping once each thread has its chunk's total, and adding those up, so it reads the data once and writes nothing.
+     * Unlike `parallel_quicksum_simd`, it honors the scanner's simd setting, and wraps on overflow like the scans do.
+     */
+    pub fn parallel_reduce_sum(&mut self, data: &[u64]) -> Result<u64, ScanError> {
+        let sum: fn(&[u64]) -> u64 = if self.simd_enabled() {
+            helper_functions::quicksum_simd
+        } else {
+            |chunk| chunk.iter().fold(0, |acc, x| acc.wrapping_add(*x))
+        };
 
-        // we know that the threads will finish by the end of the function, hack around the lifetimes
-        let data_len = data.len();
-        let data_ptr = data.as_ptr();
-        unsafe {
-            let data = std::slice::from_raw_parts(data_ptr, data_len);
-            self.thread_pool.broadcast((data, ranges), |(index, _), (data, ranges)| -> u64 {
-                helper_functions::quicksum_simd(&data[ranges[index]..ranges[index + 1]])
-            }).gather().unwrap().into_iter().sum()
+        Ok(self.map_chunks_scoped(data, sum)?.into_iter().fold(0, u64::wrapping_add))
+    }
+
+    /**
+     * Reduces the data down to a single value with the given operator, which must be associative.  Each thread reduces
+     * its own chunk, starting from the identity, and then the partial results are combined in chunk order, so the
+     * operator does not need to be commutative.  If the operator panics, the reduction fails with
+     * `ScanError::FailedThreadInGather`.
+     */
+    pub fn parallel_reduce<T: Send + Sync>(&mut self, data: &[T], identity: T, func: fn(&T, &T) -> T) -> Result<T, ScanError> {
+        let partials = self.map_chunks_scoped(data, |chunk| {
+            chunk.iter().fold(None, |acc: Option<T>, x| Some(func(acc.as_ref().unwrap_or(&identity), x)))
+        })?;
+
+        Ok(partials.into_iter().flatten().fold(identity, |acc, partial| func(&acc, &partial)))
+    }
+
+    /**
+     * The same as `parallel_reduce`, for operators without an identity.  Each chunk's fold starts from its first
+     * element instead, and the partials are combined in chunk order, so the operator still only needs to be associative.
+     * Empty data reduces to `T::default()`.
+     */
+    pub fn parallel_reduce_generic<T: Clone + Default + Send + Sync>(&mut self, data: &[T], func: fn(&T, &T) -> T) -> Result<T, ScanError> {
+        let partials = self.map_chunks_scoped(data, |chunk| {
+            chunk[1..].iter().fold(chunk[0].clone(), |acc, x| func(&acc, x))
+        })?;
+
+        let mut partials = partials.into_iter();
+        Ok(match partials.next() {
+            Some(first) => partials.fold(first, |acc, partial| func(&acc, &partial)),
+            None => T::default()
+        })
+    }
+
+    /**
+     * Sequentially scans the data in place, dispatching to the simd kernel for its element type, or to a scalar scan if
+     * simd has been turned off.  Float data is checked for NaN and infinite values first, according to the nan policy.
+     */
+    pub fn sequential_scan<T: helper_functions::SimdScan>(&self, data: &mut [T]) -> Result<(), ScanError> {
+        match self.nan_policy {
+            NanPolicy::Propagate => (),
+            NanPolicy::Error => {
+                if let Some(index) = data.iter().position(|x| !x.is_finite()) {
+                    return Err(ScanError::NonFinite { index })
+                }
+            },
+            NanPolicy::SkipAsIdentity => {
+                for x in data.iter_mut().filter(|x| !x.is_finite()) {
+                    *x = T::default();
+                }
+            }
+        }
+
+        self.set_last_used_simd(self.simd_enabled());
+        if self.simd_enabled() {
+            T::prefix_scan_simd_width(data, self.simd_width);
+        } else {
+            T::prefix_scan_no_simd(data);
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Computes the inclusive scan of the vector, picking the algorithm by its length.  Inputs shorter than
+     * `parallel_length`, or any input on a single thread, are scanned sequentially.  Inputs shorter than
+     * `hillis_steel_length` use `hillis_steel_scan`, and everything else uses `divide_and_conquer_scan`.  The sequential
+     * and hillis steel scans can only wrap on overflow, so any other overflow mode always uses the divide and conquer scan.
+     */
+    pub fn scan(&mut self, vec: Vec<u64>) -> Result<Vec<u64>, ScanError> {
+        self.verified_scan(vec, Self::dispatch_scan)
+    }
+
+    /**
+     * Runs the scan, and if the scanner is verifying, checks it against a scalar scan of a copy of the input, with the
+     * same overflow mode.
+     */
+    fn verified_scan(&mut self, vec: Vec<u64>, scan: fn(&mut Self, Vec<u64>) -> Result<Vec<u64>, ScanError>) -> Result<Vec<u64>, ScanError> {
+        if !(cfg!(debug_assertions) && self.verify) {
+            return scan(self, vec)
+        }
+
+        let mut baseline = vec.clone();
+        let output = scan(self, vec)?;
+        let overflow = self.overflow;
+        helper_functions::prefix_scan_overflowing(&mut baseline, |a, b| overflow.add(a, b)).ok_or(ScanError::Overflow)?;
+
+        match output.iter().zip(baseline.iter()).position(|(x, y)| x != y) {
+            Some(index) => Err(ScanError::VerificationFailed { index }),
+            None if output.len() != baseline.len() => Err(ScanError::VerificationFailed { index: output.len().min(baseline.len()) }),
+            None => Ok(output)
+        }
+    }
+
+    fn dispatch_scan(&mut self, mut vec: Vec<u64>) -> Result<Vec<u64>, ScanError> {
+        if self.overflow != ScanOverflow::Wrapping {
+            return self.divide_and_conquer_scan(vec)
+        }
+
+        if vec.len() < self.parallel_length || self.num_threads() == 1 {
+            self.sequential_scan(&mut vec)?;
+            Ok(vec)
+        } else if vec.len() < self.hillis_steel_length {
+            self.hillis_steel_scan(vec)
+        } else {
+            self.divide_and_conquer_scan(vec)
+        }
+    }
+
+    /**
+     * The same as `scan`, but leaves the result in the caller's vector.  The vector is moved through the scan rather
+     * than copied, and the same allocation comes back out of it, so scanning one buffer over and over never allocates.
+     * The exception is `hillis_steel_scan`, which ping pongs between the vector and the workspace, and may hand back
+     * the other one.  If the scan fails, the vector is left empty.
+     */
+    pub fn scan_in_place(&mut self, vec: &mut Vec<u64>) -> Result<(), ScanError> {
+        *vec = self.scan(std::mem::take(vec))?;
+        Ok(())
+    }
+
+    /**
+     * Counts the trues up to and including each position.  The bools are widened to u64s in one pass, which compiles down
+     * to vector zero extensions of the bytes, and then scanned with `scan`.  See `prefix_count` for the exclusive count.
+     */
+    pub fn prefix_sum_bool(&mut self, data: &[bool]) -> Result<Vec<u64>, ScanError> {
+        self.scan(data.iter().map(|&x| x as u64).collect())
+    }
+
+    /**
+     * Scans the data, and also finds where each run of equal values in it starts, for run length encoding.  The run
+     * starts are found in a pass of their own, before the data is scanned, so this reads the data once more than `scan`
+     * does.  That pass is split over the executor's threads, with each chunk's first run only a new one if it differs
+     * from the end of the chunk before.  The first run starts at 0, unless there's no data.
+     */
+    pub fn scan_with_runs(&mut self, data: Vec<u64>) -> Result<(Vec<u64>, Vec<usize>), ScanError> {
+        let chunks = self.map_chunks_scoped(&data, |chunk| {
+            let starts = (1..chunk.len()).filter(|&i| chunk[i - 1] != chunk[i]).collect::<Vec<_>>();
+            (chunk.len(), chunk.first().copied(), chunk.last().copied(), starts)
+        })?;
+
+        let mut runs = Vec::new();
+        let mut offset = 0;
+        let mut previous = None;
+        for (len, first, last, starts) in chunks {
+            if first.is_some() && first != previous {
+                runs.push(offset);
+            }
+            runs.extend(starts.into_iter().map(|i| i + offset));
+            offset += len;
+            previous = last;
         }
+
+        Ok((self.scan(data)?, runs))
     }
 }
 
@@ -104,6 +795,404 @@ mod test {
     #[test]
     fn parallel_quicksum_test() {
         let vec = (0..35).collect::<Vec<_>>();
-        assert_eq!(prefix_scans::Scanner::new().with_threads(4).parallel_quicksum_simd(&vec), vec.iter().sum());        
+        assert_eq!(prefix_scans::Scanner::new().with_threads(4).parallel_quicksum_simd(&vec).unwrap(), vec.iter().sum::<u64>());        
+    }
+
+    #[test]
+    fn scan_in_place_test() {
+        for parallel_length in [0, usize::MAX] {
+            let mut scanner = prefix_scans::Scanner::new().with_threads(4).with_parallel_length(parallel_length);
+            let mut vec = (0..100000).collect::<Vec<u64>>();
+            let baseline = prefix_scans::baseline::sequential_scan_no_simd(vec.clone(), |a, b| a + b).unwrap();
+            let buffer = vec.as_ptr();
+
+            scanner.scan_in_place(&mut vec).unwrap();
+            assert_eq!(vec, baseline);
+            // the results are in the caller's own allocation, not a copy of it
+            assert_eq!(vec.as_ptr(), buffer);
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn rayon_scan_test() {
+        let vec = (0..300000).collect::<Vec<u64>>();
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(vec.clone(), |a, b| a + b).unwrap();
+        let mut scanner = prefix_scans::Scanner::new().with_rayon().with_threads(4).with_cache_chunk_length(100000);
+        assert_eq!(scanner.divide_and_conquer_scan(vec.clone()).unwrap(), baseline);
+        assert_eq!(scanner.blelloch_scan(vec.clone()).unwrap()[1..], baseline[..baseline.len() - 1]);
+        assert_eq!(scanner.clone().hillis_steel_scan(vec).unwrap(), baseline);
+    }
+
+    #[test]
+    fn set_threads_test() {
+        let mut scanner = prefix_scans::Scanner::new().with_work_stealing(true).with_parallel_length(0);
+        let vec = (0..10000).collect::<Vec<u64>>();
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(vec.clone(), |a, b| a + b).unwrap();
+        for num_threads in [3, 3, 8, 0, 2] {
+            scanner.set_threads(num_threads);
+            assert_eq!(scanner.num_threads(), std::cmp::max(num_threads, 1));
+            assert!(scanner.thread_pool.work_stealing());
+            assert_eq!(scanner.scan(vec.clone()).unwrap(), baseline);
+        }
+    }
+
+    #[test]
+    fn min_chunk_size_test() {
+        let scanner = prefix_scans::Scanner::new().with_threads(8).with_min_chunk_size(64);
+        assert!(scanner.num_chunks(100) <= 2);
+        assert_eq!(scanner.num_chunks(10), 1);
+        assert_eq!(scanner.num_chunks(100000), 8);
+        assert_eq!(prefix_scans::Scanner::new().with_threads(8).with_min_chunk_size(0).num_chunks(5), 5);
+
+        let mut scanner = scanner.with_parallel_length(0);
+        for len in [0, 1, 100, 5000, 100003] {
+            let vec = (0..len).collect::<Vec<u64>>();
+            let baseline = prefix_scans::baseline::sequential_scan_no_simd(vec.clone(), |a, b| a + b).unwrap();
+            assert_eq!(scanner.divide_and_conquer_scan(vec.clone()).unwrap(), baseline);
+            assert_eq!(scanner.parallel_reduce_sum(&vec).unwrap(), vec.iter().sum::<u64>());
+        }
+    }
+
+    #[test]
+    fn parallel_quicksum_many_threads_test() {
+        let mut scanner = prefix_scans::Scanner::new().with_threads(32);
+        for len in [0, 1, 31, 32, 33, 1000, 100003] {
+            let vec = (0..len).collect::<Vec<u64>>();
+            for _ in 0..10 {
+                assert_eq!(scanner.parallel_quicksum_simd(&vec).unwrap(), vec.iter().sum::<u64>());
+            }
+        }
+    }
+
+    #[test]
+    fn parallel_reduce_sum_test() {
+        for scanner in [prefix_scans::Scanner::new(), prefix_scans::Scanner::new().without_simd()] {
+            let mut scanner = scanner.with_threads(4);
+            for len in [0, 1, 7, 1000, 100003] {
+                let vec = (0..len).collect::<Vec<u64>>();
+                assert_eq!(scanner.parallel_reduce_sum(&vec).unwrap(), vec.iter().sum::<u64>());
+            }
+            assert_eq!(scanner.parallel_reduce_sum(&[u64::MAX, 2]).unwrap(), 1);
+        }
+    }
+
+    #[test]
+    fn parallel_reduce_test() {
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4);
+        let vec = (0..1001).collect::<Vec<u64>>();
+        assert_eq!(scanner.parallel_reduce(&vec, 0, |a, b| a + b).unwrap(), vec.iter().sum::<u64>());
+        assert_eq!(scanner.parallel_reduce(&vec, 0, |a, b| *std::cmp::max(a, b)).unwrap(), 1000);
+        assert_eq!(scanner.parallel_reduce(&[], 7, |a: &u64, b| a + b).unwrap(), 7);
+
+        // concatenation is not commutative, so this only works if the chunks are combined in order
+        let words = (0..26).map(|i| ((b'a' + i) as char).to_string()).collect::<Vec<_>>();
+        assert_eq!(scanner.parallel_reduce(&words, String::new(), |a, b| a.clone() + b).unwrap(), "abcdefghijklmnopqrstuvwxyz");
+
+        // an operator that panics on any of the threads, the calling one included, fails the reduction instead
+        let reduced = scanner.parallel_reduce(&vec, 0, |a, b| if *b == 3 { panic!("bad element") } else { a + b });
+        assert!(matches!(reduced, Err(prefix_scans::ScanError::FailedThreadInGather)));
+        let reduced = scanner.parallel_reduce(&vec, 0, |a, b| if *b == 1000 { panic!("bad element") } else { a + b });
+        assert!(matches!(reduced, Err(prefix_scans::ScanError::FailedThreadInGather)));
+    }
+
+    #[test]
+    fn parallel_reduce_generic_test() {
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4);
+        let vec = (0..1001).map(|i| i as f64 * 0.5).collect::<Vec<_>>();
+        assert_eq!(scanner.parallel_reduce_generic(&vec, |a, b| a + b).unwrap(), 250250.0);
+        // there's no identity for min to start from, so each chunk has to start from its own first element
+        assert_eq!(scanner.parallel_reduce_generic(&vec, |a, b| a.min(*b)).unwrap(), 0.0);
+        assert_eq!(scanner.parallel_reduce_generic(&[], |a: &f64, b| a + b).unwrap(), 0.0);
+
+        // concatenation is not commutative, so this only works if the chunks are combined in order
+        let words = (0..26).map(|i| ((b'a' + i) as char).to_string()).collect::<Vec<_>>();
+        assert_eq!(scanner.parallel_reduce_generic(&words, |a, b| a.clone() + b).unwrap(), "abcdefghijklmnopqrstuvwxyz");
+        assert_eq!(scanner.parallel_reduce_generic(&words[..1], |a, b| a.clone() + b).unwrap(), "a");
+    }
+
+    #[test]
+    fn sequential_scan_dispatch_test() {
+        let unsigned = (0..100).collect::<Vec<u32>>();
+        let signed = (0..100).map(|i| 50 - i).collect::<Vec<i32>>();
+        let unsigned_baseline = prefix_scans::baseline::sequential_scan_no_simd(unsigned.clone(), |a, b| a + b).unwrap();
+        let signed_baseline = prefix_scans::baseline::sequential_scan_no_simd(signed.clone(), |a, b| a + b).unwrap();
+
+        for scanner in [prefix_scans::Scanner::new(), prefix_scans::Scanner::new().without_simd()] {
+            let (mut unsigned, mut signed) = (unsigned.clone(), signed.clone());
+            scanner.sequential_scan(&mut unsigned).unwrap();
+            scanner.sequential_scan(&mut signed).unwrap();
+            assert_eq!(unsigned, unsigned_baseline);
+            assert_eq!(signed, signed_baseline);
+
+            // both the kernels and the scalar fallback wrap on overflow
+            let mut wrapping = vec![u32::MAX, 2, 3];
+            scanner.sequential_scan(&mut wrapping).unwrap();
+            assert_eq!(wrapping, vec![u32::MAX, 1, 4]);
+            let mut wrapping = vec![i32::MAX, 1, i32::MIN];
+            scanner.sequential_scan(&mut wrapping).unwrap();
+            assert_eq!(wrapping, vec![i32::MAX, i32::MIN, 0]);
+            let mut wrapping = vec![u128::MAX, 1];
+            scanner.sequential_scan(&mut wrapping).unwrap();
+            assert_eq!(wrapping, vec![u128::MAX, 0]);
+        }
+    }
+
+    #[test]
+    fn simd_toggle_test() {
+        let mut scanner = prefix_scans::Scanner::new().without_simd().with_simd(true);
+        assert!(scanner.simd_on);
+        assert_eq!(scanner.simd_enabled(), prefix_scans::helper_functions::simd_detected());
+
+        scanner.set_simd(false);
+        assert!(!scanner.simd_on);
+        assert!(!scanner.simd_enabled());
+
+        scanner.set_simd(true);
+        assert!(!scanner.with_simd(false).simd_on);
+    }
+
+    #[test]
+    fn zero_config_test() {
+        assert_eq!(format!("{:?}", prefix_scans::Scanner::default()), format!("{:?}", prefix_scans::Scanner::new()));
+
+        let mut scanner = prefix_scans::Scanner::default().with_threads(0).with_cache_chunk_length(0);
+        assert_eq!(scanner.num_threads(), 1);
+        assert_eq!(scanner.cache_chunk_length, 1);
+        assert_eq!(scanner.divide_and_conquer_scan(vec![1, 2, 3]).unwrap(), vec![1, 3, 6]);
+
+        scanner.set_cache_chunk_length(0);
+        assert_eq!(scanner.cache_chunk_length, 1);
+    }
+
+    #[test]
+    fn work_stealing_scan_test() {
+        let mut scanner = prefix_scans::Scanner::new().with_work_stealing(true).with_threads(4).with_cache_chunk_length(1000);
+        assert!(scanner.clone().thread_pool.work_stealing());
+
+        let list = (0..10000).collect::<Vec<u64>>();
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a + b).unwrap();
+        assert_eq!(scanner.divide_and_conquer_scan(list.clone()).unwrap(), baseline);
+        assert_eq!(scanner.blelloch_scan(list).unwrap().last(), baseline.get(9998));
+    }
+
+    #[test]
+    fn clone_test() {
+        let mut scanner = prefix_scans::Scanner::new()
+            .with_threads(3)
+            .without_simd()
+            .with_sequential_length(17)
+            .with_cache_chunk_length(1024)
+            .with_overflow(prefix_scans::ScanOverflow::Saturating);
+        scanner.reserve(100);
+        let mut cloned = scanner.clone();
+        assert_eq!(format!("{:?}", cloned), format!("{:?}", scanner));
+        assert!(format!("{:?}", cloned).contains("num_threads: 3"));
+        assert_eq!(cloned.workspace.capacity(), 0);
+
+        // the clone's pool is its own, so both can scan at once, and it outlives the original
+        let list = (0..10000).collect::<Vec<u64>>();
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a + b).unwrap();
+        let handle = {
+            let list = list.clone();
+            std::thread::spawn(move || cloned.divide_and_conquer_scan(list).unwrap())
+        };
+        assert_eq!(scanner.divide_and_conquer_scan(list.clone()).unwrap(), baseline);
+        drop(scanner);
+        assert_eq!(handle.join().unwrap(), baseline);
+    }
+
+    #[test]
+    fn nan_policy_test() {
+        let data = vec![1.0, 2.0, f64::NAN, 3.0, f64::INFINITY, 4.0];
+
+        let mut propagated = data.clone();
+        prefix_scans::Scanner::new().sequential_scan(&mut propagated).unwrap();
+        assert_eq!(propagated[..2], [1.0, 3.0]);
+        assert!(propagated[2..].iter().all(|x| x.is_nan()));
+
+        let mut errored = data.clone();
+        let scanner = prefix_scans::Scanner::new().with_nan_policy(prefix_scans::NanPolicy::Error);
+        assert!(matches!(scanner.sequential_scan(&mut errored), Err(prefix_scans::ScanError::NonFinite { index: 2 })));
+
+        let mut skipped = data.clone();
+        let scanner = prefix_scans::Scanner::new().with_nan_policy(prefix_scans::NanPolicy::SkipAsIdentity);
+        scanner.sequential_scan(&mut skipped).unwrap();
+        assert_eq!(skipped, vec![1.0, 3.0, 3.0, 6.0, 6.0, 10.0]);
+
+        // integers are always finite, so the policy never gets in the way
+        let mut ints = vec![1u64, 2, 3];
+        scanner.sequential_scan(&mut ints).unwrap();
+        assert_eq!(ints, vec![1, 3, 6]);
+    }
+
+    #[test]
+    fn prefix_sum_bool_test() {
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4).with_parallel_length(0);
+        assert_eq!(scanner.prefix_sum_bool(&[true, false, true, true]).unwrap(), vec![1, 1, 2, 3]);
+        assert_eq!(scanner.prefix_sum_bool(&[]).unwrap(), Vec::<u64>::new());
+
+        let data = (0..10007).map(|i| i % 3 == 0).collect::<Vec<_>>();
+        let counts = scanner.prefix_sum_bool(&data).unwrap();
+        assert!(counts.iter().enumerate().all(|(i, &count)| count == (i as u64) / 3 + 1));
+    }
+
+    #[test]
+    fn scan_with_runs_test() {
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4);
+        let (scanned, runs) = scanner.scan_with_runs(vec![1, 1, 2, 2, 2, 3]).unwrap();
+        assert_eq!(scanned, vec![1, 2, 4, 6, 8, 11]);
+        assert_eq!(runs, vec![0, 2, 5]);
+        assert_eq!(scanner.scan_with_runs(vec![]).unwrap(), (vec![], vec![]));
+
+        // runs that cross the chunk boundaries, and ones that start right on them
+        let data = (0..1003).map(|i| i / 7).collect::<Vec<u64>>();
+        let (_, runs) = scanner.scan_with_runs(data).unwrap();
+        assert_eq!(runs, (0..1003).step_by(7).collect::<Vec<_>>());
+        let data = (0..1000).map(|i| i / 250).collect::<Vec<u64>>();
+        let (_, runs) = scanner.with_chunk_strategy(prefix_scans::chunk_strategy::EvenChunkStrategy).scan_with_runs(data).unwrap();
+        assert_eq!(runs, vec![0, 250, 500, 750]);
+    }
+
+    #[test]
+    fn config_test() {
+        assert_eq!(prefix_scans::ScannerConfig::default(), prefix_scans::Scanner::new().config());
+
+        let config = prefix_scans::ScannerConfig {
+            num_threads: 3,
+            simd_on: false,
+            simd_width: prefix_scans::helper_functions::SimdWidth::Lanes16,
+            sequential_length: 100,
+            cache_chunk_length: 1000,
+            parallel_tiles: true,
+            parallel_length: 50,
+            hillis_steel_length: 10,
+            min_chunk_size: 7,
+            overflow: prefix_scans::ScanOverflow::Saturating,
+            nan_policy: prefix_scans::NanPolicy::Error,
+            verify: true,
+            thread_affinity: true,
+            work_stealing: true
+        };
+        let mut scanner = prefix_scans::Scanner::from_config(config.clone());
+        assert_eq!(scanner.config(), config);
+        assert_eq!(scanner.num_threads(), 3);
+
+        let list = (0..5000).collect::<Vec<u64>>();
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a + b).unwrap();
+        assert_eq!(scanner.scan(list).unwrap(), baseline);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn config_serde_test() {
+        let config = prefix_scans::Scanner::new().with_threads(4).with_overflow(prefix_scans::ScanOverflow::Checked).config();
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(serde_json::from_str::<prefix_scans::ScannerConfig>(&json).unwrap(), config);
+
+        // settings missing from the file keep their defaults
+        let partial = serde_json::from_str::<prefix_scans::ScannerConfig>(r#"{ "num_threads": 2, "verify": true }"#).unwrap();
+        assert_eq!(partial, prefix_scans::ScannerConfig { num_threads: 2, verify: true, ..Default::default() });
+    }
+
+    #[test]
+    fn last_used_simd_test() {
+        let list = (0..100000).collect::<Vec<u64>>();
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4).with_parallel_length(0);
+        assert!(!scanner.last_used_simd());
+        scanner.scan(list.clone()).unwrap();
+        assert_eq!(scanner.last_used_simd(), prefix_scans::helper_functions::simd_detected());
+        scanner.hillis_steel_scan(list.clone()).unwrap();
+        assert!(!scanner.last_used_simd());
+
+        let mut scanner = scanner.without_simd();
+        scanner.scan(list.clone()).unwrap();
+        assert!(!scanner.last_used_simd());
+        scanner.sequential_scan(&mut list.clone()).unwrap();
+        assert!(!scanner.last_used_simd());
+
+        let mut scanner = prefix_scans::Scanner::new().with_overflow(prefix_scans::ScanOverflow::Checked);
+        scanner.scan(list).unwrap();
+        assert!(!scanner.last_used_simd());
+    }
+
+    #[test]
+    fn sequential_test() {
+        let mut scanner = prefix_scans::Scanner::sequential();
+        assert_eq!(scanner.num_threads(), 1);
+
+        // small values after a large one, so that the order of the additions changes how they round
+        let floats = (0..1003).map(|i| if i % 16 == 0 { 1e16 } else { 0.1 * i as f64 }).collect::<Vec<_>>();
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(floats.clone(), |a, b| a + b).unwrap();
+        let mut scanned = floats.clone();
+        scanner.sequential_scan(&mut scanned).unwrap();
+        assert!(scanned.iter().zip(baseline.iter()).all(|(x, y)| x.to_bits() == y.to_bits()));
+
+        let ints = (0..100003).collect::<Vec<u64>>();
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(ints.clone(), |a, b| a + b).unwrap();
+        assert_eq!(scanner.scan(ints).unwrap(), baseline);
+    }
+
+    // verification is compiled out without debug assertions
+    #[test]
+    #[cfg(debug_assertions)]
+    fn verify_test() {
+        let mut scanner = prefix_scans::Scanner::new()
+            .with_threads(4)
+            .with_parallel_length(100)
+            .with_hillis_steel_length(1000)
+            .with_cache_chunk_length(512)
+            .with_verify(true);
+
+        for len in [0, 1, 99, 999, 5003] {
+            let vec = (0..len).collect::<Vec<u64>>();
+            let baseline = prefix_scans::baseline::sequential_scan_no_simd(vec.clone(), |a, b| a + b).unwrap();
+            assert_eq!(scanner.scan(vec).unwrap(), baseline);
+        }
+
+        // a divide and conquer scan that gets one element wrong, and one that loses the last element
+        let vec = (0..5003).collect::<Vec<u64>>();
+        let corrupted = scanner.verified_scan(vec.clone(), |scanner, vec| {
+            let mut vec = scanner.divide_and_conquer_scan(vec)?;
+            vec[4000] += 1;
+            Ok(vec)
+        });
+        assert!(matches!(corrupted, Err(prefix_scans::ScanError::VerificationFailed { index: 4000 })));
+        let truncated = scanner.verified_scan(vec, |scanner, vec| {
+            let mut vec = scanner.divide_and_conquer_scan(vec)?;
+            vec.pop();
+            Ok(vec)
+        });
+        assert!(matches!(truncated, Err(prefix_scans::ScanError::VerificationFailed { index: 5002 })));
+    }
+
+    #[test]
+    fn scan_dispatch_test() {
+        // small thresholds, so that each of the algorithms gets picked for some of the lengths
+        let mut scanner = prefix_scans::Scanner::new()
+            .with_threads(4)
+            .with_parallel_length(100)
+            .with_hillis_steel_length(1000)
+            .with_cache_chunk_length(512);
+
+        for len in [0, 1, 7, 99, 100, 101, 999, 1000, 5003] {
+            let vec = (0..len).collect::<Vec<u64>>();
+            let baseline = prefix_scans::baseline::sequential_scan_no_simd(vec.clone(), |a, b| a + b).unwrap();
+            assert_eq!(scanner.scan(vec).unwrap(), baseline);
+        }
+    }
+
+    #[test]
+    fn scan_dispatch_wrapping_test() {
+        // every length goes to the hillis steel scan, which has to wrap the same as the others
+        let mut scanner = prefix_scans::Scanner::new()
+            .with_threads(4)
+            .with_parallel_length(0)
+            .with_hillis_steel_length(10000);
+
+        let mut vec = (0..1000).collect::<Vec<u64>>();
+        vec[10] = u64::MAX;
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(vec.clone(), |a, b| a.wrapping_add(*b)).unwrap();
+        assert_eq!(scanner.scan(vec).unwrap(), baseline);
     }
 }
\ No newline at end of file
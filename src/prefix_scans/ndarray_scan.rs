@@ -0,0 +1,63 @@
+use ndarray::{Array2, Axis};
+
+use crate::prefix_scans::{Scanner, ScanError};
+use crate::prefix_scans::segmented_scan::HeadFlagVec;
+
+
+impl Scanner {
+    /**
+     * Scans each lane of the array along the axis, so `Axis(0)` sums down the columns, and `Axis(1)` along the rows.
+     * The lanes are gathered one after another into a `HeadFlagVec`, with a head flag at the start of each, and scanned
+     * in one go with `segmented_scan_flat`.  This way the threads split up the work evenly however many lanes there are,
+     * and however long they are, and strided lanes are scanned from contiguous memory.  The results are then scattered
+     * back into the lanes.
+     */
+    pub fn scan_axis(&mut self, arr: &mut Array2<u64>, axis: Axis) -> Result<(), ScanError> {
+        let lane_length = arr.len_of(axis);
+        let data = arr.lanes(axis).into_iter().flatten().copied().collect::<Vec<_>>();
+        let head_flags = (0..data.len()).map(|i| i % lane_length == 0).collect::<Vec<_>>();
+        let vec = HeadFlagVec::new(data, head_flags).ok_or(ScanError::InvalidDimensions)?;
+
+        let (data, _) = self.segmented_scan_flat(vec)?.into_parts();
+        for (x, scanned) in arr.lanes_mut(axis).into_iter().flatten().zip(data) {
+            *x = scanned;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ndarray::{Array2, Axis};
+
+    use crate::prefix_scans;
+
+    #[test]
+    fn scan_axis_test() {
+        let arr = Array2::from_shape_fn((37, 53), |(i, j)| (i * 53 + j) as u64 % 17);
+        for axis in [Axis(0), Axis(1)] {
+            let mut baseline = arr.clone();
+            for mut lane in baseline.lanes_mut(axis) {
+                for i in 1..lane.len() {
+                    lane[i] += lane[i - 1];
+                }
+            }
+
+            for num_threads in [1, 4] {
+                let mut scanner = prefix_scans::Scanner::new().with_threads(num_threads);
+                let mut scanned = arr.clone();
+                scanner.scan_axis(&mut scanned, axis).unwrap();
+                assert_eq!(scanned, baseline);
+
+                // a transposed view has the same lanes, but strided the other way
+                let mut transposed = arr.t().to_owned();
+                scanner.scan_axis(&mut transposed, Axis(1 - axis.index())).unwrap();
+                assert_eq!(transposed, baseline.t());
+            }
+        }
+
+        let mut empty = Array2::<u64>::zeros((0, 4));
+        prefix_scans::Scanner::new().with_threads(4).scan_axis(&mut empty, Axis(0)).unwrap();
+    }
+}
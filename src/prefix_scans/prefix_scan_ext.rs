@@ -0,0 +1,85 @@
+use crate::prefix_scans::{Scanner, ScanError};
+
+
+/**
+ * Lets slices and vectors be scanned directly, rather than through the scanner.  The scans go through `Scanner::scan`,
+ * so they pick the algorithm the same way.
+ *
+ * ```
+ * use prefix_scan::PrefixScanExt;
+ *
+ * let mut scanner = prefix_scan::Scanner::new().with_threads(4);
+ * let mut data = [1, 2, 3, 4];
+ * data.parallel_prefix_sum(&mut scanner).unwrap();
+ * assert_eq!(data, [1, 3, 6, 10]);
+ *
+ * let sums = vec![1, 1, 1].into_parallel_prefix_sum(&mut scanner).unwrap();
+ * assert_eq!(sums, vec![1, 2, 3]);
+ * ```
+ */
+pub trait PrefixScanExt {
+    /**
+     * Scans in place.
+     */
+    fn parallel_prefix_sum(&mut self, scanner: &mut Scanner) -> Result<(), ScanError>;
+
+    /**
+     * Scans, and hands the result back.
+     */
+    fn into_parallel_prefix_sum(mut self, scanner: &mut Scanner) -> Result<Self, ScanError>
+    where Self: Sized {
+        self.parallel_prefix_sum(scanner)?;
+        Ok(self)
+    }
+}
+
+impl PrefixScanExt for [u64] {
+    /**
+     * The scans need a vector to move between threads, so the slice is scanned in the scanner's workspace, and copied
+     * back.
+     */
+    fn parallel_prefix_sum(&mut self, scanner: &mut Scanner) -> Result<(), ScanError> {
+        let mut vec = scanner.take_workspace();
+        vec.extend_from_slice(self);
+        let vec = scanner.scan(vec)?;
+        self.copy_from_slice(&vec);
+        scanner.return_workspace(vec);
+        Ok(())
+    }
+}
+
+impl PrefixScanExt for Vec<u64> {
+    fn parallel_prefix_sum(&mut self, scanner: &mut Scanner) -> Result<(), ScanError> {
+        scanner.scan_in_place(self)
+    }
+
+    /**
+     * The vector is moved through the scan, so unlike scanning a slice, nothing is copied.
+     */
+    fn into_parallel_prefix_sum(self, scanner: &mut Scanner) -> Result<Self, ScanError> {
+        scanner.scan(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prefix_scans;
+    use crate::prefix_scans::PrefixScanExt;
+
+    #[test]
+    fn prefix_scan_ext_test() {
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4).with_parallel_length(0);
+        let vec = (0..1000).collect::<Vec<u64>>();
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(vec.clone(), |a, b| a + b).unwrap();
+
+        let mut slice = vec.clone();
+        slice[..].parallel_prefix_sum(&mut scanner).unwrap();
+        assert_eq!(slice, baseline);
+
+        let mut in_place = vec.clone();
+        in_place.parallel_prefix_sum(&mut scanner).unwrap();
+        assert_eq!(in_place, baseline);
+
+        assert_eq!(vec.into_parallel_prefix_sum(&mut scanner).unwrap(), baseline);
+    }
+}
@@ -0,0 +1,75 @@
+use crate::prefix_scans::{Scanner, ScanError};
+use crate::prefix_scans::helper_functions;
+use crate::util::split_vector;
+
+
+impl Scanner {
+    /**
+     * The parallel version of `helper_functions::inclusive_to_exclusive`, for inputs at least `parallel_length` long.
+     * Each thread shifts its own chunk, and the element shifted into the start of each chunk is the last element of the
+     * one before it, which is read before any of the chunks are shifted.
+     */
+    pub fn inclusive_to_exclusive(&mut self, data: &mut [u64], identity: u64) -> Result<(), ScanError> {
+        if data.len() <= 1 || data.len() < self.parallel_length || self.num_threads() == 1 {
+            helper_functions::inclusive_to_exclusive(data, identity);
+            return Ok(())
+        }
+
+        let ranges = helper_functions::chunk_ranges(data.len(), self.num_chunks(data.len()));
+        let firsts = std::iter::once(identity).chain(ranges[1..ranges.len() - 1].iter().map(|&i| data[i - 1])).collect::<Vec<_>>();
+        let mut data = split_vector::SplitVector::from_mut_slice(data);
+        let work = data.chunk(&ranges)?.into_iter().zip(firsts).collect::<Vec<_>>();
+        self.map_on_executor_scoped(work, |_, (mut chunk, first)| helper_functions::inclusive_to_exclusive(&mut chunk, first))?;
+        Ok(())
+    }
+
+    /**
+     * The parallel version of `helper_functions::exclusive_to_inclusive`, for inputs at least `parallel_length` long.
+     * Fails with `ScanError::InvalidDimensions` if the original is a different length.  Each chunk is sent with the range
+     * of the original it lines up with, which it reads from the shared borrow of the whole original.
+     */
+    pub fn exclusive_to_inclusive(&mut self, data: &mut [u64], original: &[u64]) -> Result<(), ScanError> {
+        if data.len() != original.len() {
+            return Err(ScanError::InvalidDimensions)
+        }
+        if data.len() < self.parallel_length || self.num_threads() == 1 {
+            helper_functions::exclusive_to_inclusive(data, original);
+            return Ok(())
+        }
+
+        let ranges = helper_functions::chunk_ranges(data.len(), self.num_chunks(data.len()));
+        let mut data = split_vector::SplitVector::from_mut_slice(data);
+        let work = data.chunk(&ranges)?.into_iter().zip(ranges.windows(2).map(|range| range[0]..range[1])).collect::<Vec<_>>();
+        self.map_on_executor_scoped(work, |_, (mut chunk, range)| helper_functions::exclusive_to_inclusive(&mut chunk, &original[range]))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prefix_scans;
+
+    #[test]
+    fn round_trip_test() {
+        for parallel_length in [0, usize::MAX] {
+            let mut scanner = prefix_scans::Scanner::new().with_threads(4).with_parallel_length(parallel_length);
+            for len in [0, 1, 2, 3, 1003] {
+                let original = (1..=len).collect::<Vec<u64>>();
+                let inclusive = prefix_scans::baseline::sequential_scan_no_simd(original.clone(), |a, b| a + b).unwrap();
+
+                let mut data = inclusive.clone();
+                scanner.inclusive_to_exclusive(&mut data, 0).unwrap();
+                assert_eq!(data, std::iter::once(0).chain(inclusive.iter().copied()).take(len as usize).collect::<Vec<_>>());
+
+                scanner.exclusive_to_inclusive(&mut data, &original).unwrap();
+                assert_eq!(data, inclusive);
+            }
+
+            assert!(matches!(scanner.exclusive_to_inclusive(&mut [1, 2], &[1]), Err(prefix_scans::ScanError::InvalidDimensions)));
+        }
+
+        let mut data = vec![1, 3, 6];
+        prefix_scans::helper_functions::inclusive_to_exclusive(&mut data, 10);
+        assert_eq!(data, vec![10, 1, 3]);
+    }
+}
@@ -0,0 +1,84 @@
+use std::vec;
+
+use crate::prefix_scans::{Scanner, ScanError};
+
+
+/**
+ * An iterator over the running accumulations of another iterator, like `Iterator::scan`, but with the operators the
+ * scans take.  The scan is inclusive, so each item is the accumulation of every element up to and including its own.
+ * Built lazily with `scan_iter`, or eagerly with the parallel scan through `ScanIter::from_parallel`.
+ */
+pub struct ScanIter<I, T> {
+    iter: I,
+    acc: T,
+    func: fn(&T, &T) -> T,
+    // whether the items have already been scanned, and only need to be passed on
+    scanned: bool
+}
+
+/**
+ * Lazily scans the iterator, starting from the identity, on the calling thread.
+ */
+pub fn scan_iter<I: Iterator<Item = T>, T>(iter: I, identity: T, func: fn(&T, &T) -> T) -> ScanIter<I, T> {
+    ScanIter { iter, acc: identity, func, scanned: false }
+}
+
+impl ScanIter<vec::IntoIter<u64>, u64> {
+    /**
+     * Scans the whole vector up front with `Scanner::scan`, and then yields its sums.
+     */
+    pub fn from_parallel(scanner: &mut Scanner, vec: Vec<u64>) -> Result<Self, ScanError> {
+        Ok(Self { iter: scanner.scan(vec)?.into_iter(), acc: 0, func: |a, b| a.wrapping_add(*b), scanned: true })
+    }
+}
+
+impl<I: Iterator<Item = T>, T: Clone> Iterator for ScanIter<I, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let x = self.iter.next()?;
+        if self.scanned {
+            return Some(x)
+        }
+
+        self.acc = (self.func)(&self.acc, &x);
+        Some(self.acc.clone())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::prefix_scans;
+    use crate::prefix_scans::ScanIter;
+
+    #[test]
+    fn scan_iter_test() {
+        let sums = prefix_scans::scan_iter(1..=4u64, 0, |a, b| a + b).collect::<Vec<_>>();
+        assert_eq!(sums, vec![1, 3, 6, 10]);
+        assert_eq!(prefix_scans::scan_iter(std::iter::empty(), 0, |a: &u64, b| a + b).count(), 0);
+
+        // it's lazy, so it works on an infinite iterator
+        let products = prefix_scans::scan_iter(1.., 1, |a: &u64, b| a * b).take(5).collect::<Vec<_>>();
+        assert_eq!(products, vec![1, 2, 6, 24, 120]);
+
+        let words = prefix_scans::scan_iter(["a", "b", "c"].iter().map(|word| word.to_string()), String::new(), |a, b| a.clone() + b);
+        assert_eq!(words.collect::<Vec<_>>(), vec!["a", "ab", "abc"]);
+    }
+
+    #[test]
+    fn from_parallel_test() {
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4).with_parallel_length(0);
+        let vec = (0..1000).collect::<Vec<u64>>();
+        let lazy = prefix_scans::scan_iter(vec.clone().into_iter(), 0, |a, b| a + b).collect::<Vec<_>>();
+
+        let parallel = ScanIter::from_parallel(&mut scanner, vec).unwrap();
+        assert_eq!(parallel.size_hint(), (1000, Some(1000)));
+        assert_eq!(parallel.collect::<Vec<_>>(), lazy);
+        assert_eq!(ScanIter::from_parallel(&mut scanner, Vec::new()).unwrap().count(), 0);
+    }
+}
\ No newline at end of file
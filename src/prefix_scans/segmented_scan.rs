@@ -0,0 +1,287 @@
+use std::sync::Arc;
+
+use crate::prefix_scans::{Scanner, ScanError};
+use crate::prefix_scans::helper_functions;
+use crate::util::split_vector;
+
+
+/**
+ * A flattened vector of segments.  Each element has a head flag, which is set if it's the first element of a segment:
+ *      [[1, 2], [3], [4, 5, 6]]
+ * becomes
+ *      data:       [1, 2, 3, 4, 5, 6]
+ *      head_flags: [t, f, t, t, f, f]
+ * Empty segments have no elements to flag, so they don't survive the flattening.
+ */
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeadFlagVec<T> {
+    data: Vec<T>,
+    head_flags: Vec<bool>
+}
+
+impl<T: Clone> HeadFlagVec<T> {
+    /**
+     * Pairs up the data with its head flags.  Returns None if they aren't the same length.
+     */
+    pub fn new(data: Vec<T>, head_flags: Vec<bool>) -> Option<Self> {
+        if data.len() != head_flags.len() {
+            return None
+        }
+
+        Some(Self { data, head_flags })
+    }
+
+    pub fn from_vec(v: &[Vec<T>]) -> Self {
+        let data = v.iter().flatten().cloned().collect::<Vec<_>>();
+        let head_flags = v.iter().flat_map(|segment| (0..segment.len()).map(|i| i == 0)).collect::<Vec<_>>();
+        Self { data, head_flags }
+    }
+
+    /**
+     * Splits the data back up into its segments.  Returns None if the data and head flags aren't the same length, or if
+     * the first element isn't flagged as a head, since it then has no segment to belong to.
+     */
+    pub fn to_vec(&self) -> Option<Vec<Vec<T>>> {
+        if self.data.len() != self.head_flags.len() {
+            return None
+        }
+
+        let mut vec: Vec<Vec<T>> = Vec::new();
+        for (x, &head_flag) in self.data.iter().zip(self.head_flags.iter()) {
+            if head_flag {
+                vec.push(vec![x.clone()]);
+            } else {
+                vec.last_mut()?.push(x.clone());
+            }
+        }
+
+        Some(vec)
+    }
+
+    /**
+     * Gets the element at the index, along with its head flag.
+     */
+    pub fn get(&self, index: usize) -> Option<(&T, bool)> {
+        Some((self.data.get(index)?, *self.head_flags.get(index)?))
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn into_parts(self) -> (Vec<T>, Vec<bool>) {
+        (self.data, self.head_flags)
+    }
+}
+
+/**
+ * The baseline, scanning each segment on its own.
+ */
+pub fn sequential_scan(v: &[Vec<u64>]) -> Vec<Vec<u64>> {
+    v.iter().map(|segment| {
+        let mut segment = segment.clone();
+        helper_functions::prefix_scan_no_simd(&mut segment);
+        segment
+    }).collect()
+}
+
+/**
+ * The work sent to each thread in the first sweep, (chunk, head flags, chunk start, operator).
+ */
+type ScanWork<T> = (split_vector::SplitVectorChunk<'static, T>, Arc<Vec<bool>>, usize, fn(&T, &T) -> T);
+/**
+ * The work sent to each thread in the second sweep, (chunk, head flags, chunk start, carry, operator).
+ */
+type CarryWork<T> = (split_vector::SplitVectorChunk<'static, T>, Arc<Vec<bool>>, usize, T, fn(&T, &T) -> T);
+
+impl Scanner {
+    /**
+     * Scans each segment of the flattened vector, restarting the sum at every head flag.  This works the same way as
+     * `divide_and_conquer_scan`, except that the carries don't cross head flags.  Each thread scans its chunk, then
+     * reports its last element, and whether a segment started within it.  If one did, then the last element is the sum
+     * of a segment that started in that chunk, and is carried on as is.  Otherwise the whole chunk is in the same segment
+     * as the end of the chunk before it, so its carry is added on top of that one.  In the second sweep, each chunk adds
+     * its carry to its elements up to its first head flag, where the segment carried into it ends.  The operator must be
+     * associative, and the carries are always combined on the left, so it doesn't need to be commutative.
+     */
+    pub fn segmented_scan_flat_generic<T>(&mut self, vec: HeadFlagVec<T>, func: fn(&T, &T) -> T) -> Result<HeadFlagVec<T>, ScanError>
+    where T: Clone + Default + Send + Sync + 'static {
+        if vec.is_empty() {
+            return Ok(vec)
+        }
+
+        let (data, head_flags) = vec.into_parts();
+        let head_flags = Arc::new(head_flags);
+        let ranges = self.chunk_offsets(data.len())?;
+        let mut data = split_vector::SplitVector::with_vec(data);
+
+        let chunks = data.chunk(&ranges)?.into_iter().zip(ranges.iter())
+            .map(|(chunk, &start)| (chunk, head_flags.clone(), start, func)).collect::<Vec<_>>();
+        let totals = self.map_on_executor(chunks, |_, (mut chunk, head_flags, start, func): ScanWork<T>| -> (T, bool) {
+            for i in 1..chunk.len() {
+                if !head_flags[start + i] {
+                    chunk[i] = func(&chunk[i - 1], &chunk[i]);
+                }
+            }
+
+            (chunk.last().unwrap().clone(), head_flags[start..start + chunk.len()].contains(&true))
+        })?;
+
+        // accumulate the carries, restarting them at every chunk that contains a head flag.  there's no identity to start
+        // from, so nothing is carried into the first chunk
+        let mut carry: Option<T> = None;
+        let mut carries = Vec::with_capacity(totals.len());
+        for (total, has_head_flag) in totals {
+            let next = match carry.as_ref() {
+                Some(carry) if !has_head_flag => func(carry, &total),
+                _ => total
+            };
+            carries.push(carry.replace(next));
+        }
+
+        // only the remaining chunks have anything carried into them, so the first is skipped
+        let chunks = data.chunk(&ranges[1..])?.into_iter().zip(ranges[1..].iter().zip(carries.into_iter().skip(1).flatten()))
+            .map(|(chunk, (&start, carry))| (chunk, head_flags.clone(), start, carry, func)).collect::<Vec<_>>();
+        self.map_on_executor(chunks, |_, (mut chunk, head_flags, start, carry, func): CarryWork<T>| {
+            for (i, x) in chunk.iter_mut().enumerate() {
+                if head_flags[start + i] {
+                    break
+                }
+                *x = func(&carry, x);
+            }
+        })?;
+
+        let data = data.extract().ok_or(ScanError::BrokenThreadLocking)?;
+        let head_flags = Arc::try_unwrap(head_flags).map_err(|_| ScanError::BrokenThreadLocking)?;
+        Ok(HeadFlagVec { data, head_flags })
+    }
+
+    /**
+     * Sums each segment of the flattened vector, wrapping on overflow.
+     */
+    pub fn segmented_scan_flat(&mut self, vec: HeadFlagVec<u64>) -> Result<HeadFlagVec<u64>, ScanError> {
+        self.segmented_scan_flat_generic(vec, |a, b| a.wrapping_add(*b))
+    }
+
+    /**
+     * Scans each of the vectors on its own, in parallel across all of them.  The vectors are flattened into a
+     * `HeadFlagVec` and scanned with `segmented_scan_flat`.
+     */
+    pub fn segmented_scan(&mut self, v: &[Vec<u64>]) -> Result<Vec<Vec<u64>>, ScanError> {
+        let (data, _) = self.segmented_scan_flat(HeadFlagVec::from_vec(v))?.into_parts();
+
+        // split the data back up by the original lengths rather than the head flags, so that empty vectors survive
+        let mut rest = &data[..];
+        Ok(v.iter().map(|segment| {
+            let (scanned, remaining) = rest.split_at(segment.len());
+            rest = remaining;
+            scanned.to_vec()
+        }).collect())
+    }
+
+    /**
+     * Scans the data in segments of equal consecutive keys, restarting the sum wherever the key changes, the same way
+     * `dedup` would split them.  Returns `ScanError::InvalidDimensions` if there isn't exactly one key per element.
+     */
+    pub fn scan_by_key<K: PartialEq>(&mut self, data: Vec<u64>, keys: &[K]) -> Result<Vec<u64>, ScanError> {
+        if data.len() != keys.len() {
+            return Err(ScanError::InvalidDimensions)
+        }
+
+        let head_flags = (0..keys.len()).map(|i| i == 0 || keys[i] != keys[i - 1]).collect::<Vec<_>>();
+        let vec = HeadFlagVec::new(data, head_flags).ok_or(ScanError::InvalidDimensions)?;
+        Ok(self.segmented_scan_flat(vec)?.into_parts().0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prefix_scans;
+    use crate::prefix_scans::segmented_scan;
+
+    #[test]
+    fn segmented_scan_test() {
+        // segments of varying lengths, so that chunks both span several segments and fall within a single one
+        let v = (0..40).map(|i| (0..(i * 7) % 23).collect::<Vec<u64>>()).collect::<Vec<_>>();
+        let baseline = segmented_scan::sequential_scan(&v);
+
+        for num_threads in [1, 2, 3, 8, 64] {
+            let mut scanner = prefix_scans::Scanner::new().with_threads(num_threads);
+            assert_eq!(scanner.segmented_scan(&v).unwrap(), baseline);
+        }
+
+        let mut scanner = prefix_scans::Scanner::new().with_threads(4);
+        assert_eq!(scanner.segmented_scan(&[]).unwrap(), Vec::<Vec<u64>>::new());
+        assert_eq!(scanner.segmented_scan(&[vec![], vec![1, 2], vec![]]).unwrap(), vec![vec![], vec![1, 3], vec![]]);
+    }
+
+    #[test]
+    fn scan_by_key_test() {
+        let mut scanner = prefix_scans::Scanner::new().with_threads(3);
+        let keys = ['a', 'a', 'b', 'b', 'b', 'c'];
+        assert_eq!(scanner.scan_by_key(vec![1, 2, 3, 4, 5, 6], &keys).unwrap(), vec![1, 3, 3, 7, 12, 6]);
+        // a key that comes back later starts a new segment, rather than continuing its old one
+        assert_eq!(scanner.scan_by_key(vec![1, 1, 1, 1], &[0, 0, 1, 0]).unwrap(), vec![1, 2, 1, 1]);
+        assert_eq!(scanner.scan_by_key(Vec::new(), &[0u8; 0]).unwrap(), Vec::<u64>::new());
+        assert!(matches!(scanner.scan_by_key(vec![1, 2], &keys), Err(prefix_scans::ScanError::InvalidDimensions)));
+    }
+
+    #[test]
+    fn head_flag_vec_test() {
+        let v = vec![vec![1, 2], vec![3], vec![4, 5, 6]];
+        let flat = segmented_scan::HeadFlagVec::from_vec(&v);
+        assert_eq!(flat.get(2), Some((&3, true)));
+        assert_eq!(flat.get(4), Some((&5, false)));
+        assert_eq!(flat.to_vec().unwrap(), v);
+    }
+
+    #[test]
+    fn malformed_head_flag_vec_test() {
+        let empty = segmented_scan::HeadFlagVec::<u64>::from_vec(&[]);
+        assert_eq!(empty.to_vec(), Some(vec![]));
+        assert_eq!(segmented_scan::HeadFlagVec::<u64>::new(vec![], vec![]).unwrap().to_vec(), Some(vec![]));
+
+        assert!(segmented_scan::HeadFlagVec::new(vec![1, 2], vec![true]).is_none());
+        let mismatched = segmented_scan::HeadFlagVec::<u64> { data: vec![], head_flags: vec![true, false] };
+        assert_eq!(mismatched.to_vec(), None);
+        assert_eq!(mismatched.get(0), None);
+        let mismatched = segmented_scan::HeadFlagVec { data: vec![1, 2, 3], head_flags: vec![true] };
+        assert_eq!(mismatched.to_vec(), None);
+        assert_eq!(mismatched.get(1), None);
+
+        let headless = segmented_scan::HeadFlagVec::new(vec![1, 2, 3], vec![false, true, false]).unwrap();
+        assert_eq!(headless.to_vec(), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn head_flag_vec_serde_test() {
+        let flat = segmented_scan::HeadFlagVec::from_vec(&[vec![1u64, 2], vec![3]]);
+        let json = serde_json::to_string(&flat).unwrap();
+        assert_eq!(serde_json::from_str::<segmented_scan::HeadFlagVec<u64>>(&json).unwrap(), flat);
+    }
+
+    #[test]
+    fn generic_head_flag_vec_test() {
+        let v = vec![vec![1.5, -2.0], vec![0.25], vec![4.0, 8.0, 3.0]];
+        let flat = segmented_scan::HeadFlagVec::<f64>::from_vec(&v);
+        assert_eq!(flat.to_vec().unwrap(), v);
+
+        // a segmented running max, which is associative but has no carry to add
+        let mut scanner = prefix_scans::Scanner::new().with_threads(3);
+        let scanned = scanner.segmented_scan_flat_generic(flat, |a, b| a.max(*b)).unwrap();
+        assert_eq!(scanned.to_vec().unwrap(), vec![vec![1.5, 1.5], vec![0.25], vec![4.0, 8.0, 8.0]]);
+
+        // string concatenation isn't commutative, so this checks the carries are combined on the left
+        let words = (0..20).map(|i| ((b'a' + i) as char).to_string()).collect::<Vec<_>>();
+        let flat = segmented_scan::HeadFlagVec::from_vec(&[words[..7].to_vec(), words[7..].to_vec()]);
+        let scanned = scanner.segmented_scan_flat_generic(flat, |a, b| a.clone() + b).unwrap().to_vec().unwrap();
+        assert_eq!(scanned[0].last().unwrap(), "abcdefg");
+        assert_eq!(scanned[1].last().unwrap(), "hijklmnopqrst");
+    }
+}
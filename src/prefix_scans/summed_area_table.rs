@@ -0,0 +1,117 @@
+use crate::prefix_scans::{Scanner, ScanError};
+use crate::prefix_scans::helper_functions;
+use crate::util::split_vector;
+
+
+/**
+ * Adds each element of one row into the same column of another.
+ */
+fn add_row(from: &[u64], to: &mut [u64]) {
+    for (to, from) in to.iter_mut().zip(from.iter()) {
+        *to = to.wrapping_add(*from);
+    }
+}
+
+impl Scanner {
+    /**
+     * Computes the 2d inclusive prefix sum of a row major grid in place, also known as a summed area table or integral
+     * image.  Each element becomes the sum of every element above and to the left of it, itself included.
+     * This works the same way as `divide_and_conquer_scan`, but with rows in place of elements.  The rows are divided
+     * into a block per thread.  Each thread scans each of its rows, and then sums them down its block, so that the last
+     * row of each block is its 'carry'.  The carries are then prefix summed, and each block adds the carry of the blocks
+     * above it to each of its rows:
+     *              +-------------------+
+     *              |     thread 0      |
+     *              +-------------------+   C_0
+     *              |     thread 1      |   + C_0
+     *              +-------------------+   C_1
+     *              |     thread 2      |   + C_0 + C_1
+     *              +-------------------+
+     * Since the blocks are made of whole rows, every thread only ever works on contiguous memory.
+     */
+    pub fn summed_area_table(&mut self, data: &mut [u64], width: usize, height: usize) -> Result<(), ScanError> {
+        if data.len() != width * height {
+            return Err(ScanError::InvalidDimensions)
+        }
+        if data.is_empty() {
+            return Ok(())
+        }
+
+        let prefix_scan: fn(&mut [u64]) = if self.simd_enabled() { helper_functions::prefix_scan_simd } else { helper_functions::prefix_scan_no_simd };
+
+        // divide the rows amongst the threads, and convert the row offsets to element offsets
+        let offsets = helper_functions::chunk_ranges(height, self.num_threads()).into_iter().map(|row| row * width).collect::<Vec<_>>();
+        let mut grid = self.take_workspace();
+        grid.extend_from_slice(data);
+        let mut grid = split_vector::SplitVector::with_vec(grid);
+
+        let blocks = grid.chunk(&offsets)?.into_iter().map(|block| (block, width, prefix_scan)).collect::<Vec<_>>();
+        let mut carries = self.map_on_executor(blocks, |_, (mut block, width, prefix_scan)| -> Vec<u64> {
+            for row in block.chunks_exact_mut(width) {
+                prefix_scan(row);
+            }
+
+            // sum the scanned rows down the block, each row adding in the one above it
+            for row_start in (width..block.len()).step_by(width) {
+                let (above, rest) = block[..].split_at_mut(row_start);
+                add_row(&above[row_start - width..], &mut rest[..width]);
+            }
+
+            block[block.len() - width..].to_vec()
+        })?;
+
+        // prefix sum the carries, and drop the last one, so that each carry is the sum of every block before the next one
+        for i in 1..carries.len() {
+            let (above, rest) = carries.split_at_mut(i);
+            add_row(&above[i - 1], &mut rest[0]);
+        }
+        carries.pop();
+
+        // the first block has nothing carried into it, so only the remaining blocks are distributed
+        let blocks = grid.chunk(&offsets[1..])?.into_iter().zip(carries).collect::<Vec<_>>();
+        self.map_on_executor(blocks, |_, (mut block, carry)| {
+            for row in block.chunks_exact_mut(carry.len()) {
+                add_row(&carry, row);
+            }
+        })?;
+
+        let grid = grid.take_vec().ok_or(ScanError::BrokenThreadLocking)?;
+        data.copy_from_slice(&grid);
+        self.return_workspace(grid);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prefix_scans;
+
+    #[test]
+    fn small_grid_test() {
+        let (width, height) = (7, 5);
+        let grid = (0..(width * height) as u64).collect::<Vec<_>>();
+
+        // sum up the whole rectangle above and to the left of each element
+        let mut expected = vec![0; width * height];
+        for row in 0..height {
+            for column in 0..width {
+                expected[row * width + column] = (0..=row).flat_map(|r| (0..=column).map(move |c| r * width + c)).map(|i| grid[i]).sum();
+            }
+        }
+
+        for num_threads in 1..8 {
+            let mut table = grid.clone();
+            prefix_scans::Scanner::new()
+                .with_threads(num_threads)
+                .summed_area_table(&mut table, width, height)
+                .unwrap();
+            assert_eq!(table, expected);
+        }
+    }
+
+    #[test]
+    fn mismatched_dimensions_test() {
+        let mut grid = vec![0; 10];
+        assert!(prefix_scans::Scanner::new().summed_area_table(&mut grid, 3, 3).is_err());
+    }
+}
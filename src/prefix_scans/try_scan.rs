@@ -0,0 +1,157 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::prefix_scans::{Scanner, ScanError};
+use crate::util::split_vector;
+
+
+/**
+ * Why a `try_scan` failed.  `Operator` is the operator's own error, along with the index of the element it was
+ * computing the prefix for when it failed, and `Scan` is anything that went wrong with the scan itself.
+ */
+#[derive(Debug)]
+pub enum TryScanError<E> {
+    Operator { index: usize, error: E },
+    Scan(ScanError)
+}
+
+impl<E> From<ScanError> for TryScanError<E> {
+    fn from(error: ScanError) -> Self {
+        Self::Scan(error)
+    }
+}
+
+/**
+ * The work sent to each thread in either sweep, (chunk, index of its first element, carry, operator, cancel flag).
+ */
+type TryWork<T, E> = (split_vector::SplitVectorChunk<'static, T>, usize, Option<T>, fn(&T, &T) -> Result<T, E>, Arc<AtomicBool>);
+
+/**
+ * What each thread sends back, with `Err(None)` for a chunk that gave up because another one failed.
+ */
+type TryResult<R, E> = Result<R, Option<(usize, E)>>;
+
+/**
+ * Collects the threads' results, or the failure with the lowest index out of the ones that were found.
+ */
+fn first_failure<R, E>(results: Vec<TryResult<R, E>>) -> Result<Vec<R>, TryScanError<E>> {
+    let mut values = Vec::with_capacity(results.len());
+    let mut cancelled = false;
+    let mut failure: Option<(usize, E)> = None;
+    for result in results {
+        match result {
+            Ok(value) => values.push(value),
+            Err(Some((index, error))) => if failure.as_ref().is_none_or(|(first, _)| index < *first) {
+                failure = Some((index, error));
+            },
+            Err(None) => cancelled = true
+        }
+    }
+
+    match failure {
+        Some((index, error)) => Err(TryScanError::Operator { index, error }),
+        // chunks only give up once another has failed, so there should always be a failure to go with them
+        None if cancelled => Err(TryScanError::Scan(ScanError::FailedThreadInGather)),
+        None => Ok(values)
+    }
+}
+
+impl Scanner {
+    /**
+     * Like `divide_and_conquer_scan_generic`, but with an operator that can fail, like checked arithmetic.  The first
+     * chunk to fail flags the others to stop where they are, and the scan returns its error.  Since the chunks run at
+     * the same time, that's *a* failure, not necessarily the one a sequential scan would hit first: the reported index
+     * is the lowest out of the failures found before the others stopped.  For operators whose failures are monotonic,
+     * like checked addition of unsigned integers, the later elements can fail too, but no earlier one does.
+     */
+    pub fn try_scan<T, E>(&mut self, vec: Vec<T>, func: fn(&T, &T) -> Result<T, E>) -> Result<Vec<T>, TryScanError<E>>
+    where T: Clone + Default + Send + Sync + 'static, E: Send + 'static {
+        if vec.len() <= 1 {
+            return Ok(vec)
+        }
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        let ranges = self.chunk_offsets(vec.len())?;
+        let mut data = split_vector::SplitVector::with_vec(vec);
+
+        let chunks = data.chunk(&ranges).map_err(ScanError::from)?.into_iter().zip(ranges.iter())
+            .map(|(chunk, &start)| (chunk, start, None, func, cancel.clone())).collect::<Vec<_>>();
+        let totals = self.map_on_executor(chunks, |_, (mut chunk, start, _, func, cancel): TryWork<T, E>| -> TryResult<T, E> {
+            for i in 1..chunk.len() {
+                if cancel.load(Ordering::Relaxed) {
+                    return Err(None)
+                }
+                chunk[i] = func(&chunk[i - 1], &chunk[i]).map_err(|error| {
+                    cancel.store(true, Ordering::Relaxed);
+                    Some((start + i, error))
+                })?;
+            }
+            Ok(chunk.last().unwrap().clone())
+        })?;
+        let totals = first_failure(totals)?;
+
+        // the carry into each chunk is the prefix at the end of the chunk before it, and the last chunk's total isn't needed
+        let mut carries = Vec::with_capacity(totals.len() - 1);
+        carries.push(totals[0].clone());
+        for (i, total) in totals.iter().enumerate().take(totals.len() - 1).skip(1) {
+            let carry = func(carries.last().unwrap(), total)
+                .map_err(|error| TryScanError::Operator { index: ranges[i + 1] - 1, error })?;
+            carries.push(carry);
+        }
+
+        let chunks = data.chunk(&ranges[1..]).map_err(ScanError::from)?.into_iter().zip(ranges[1..].iter()).zip(carries)
+            .map(|((chunk, &start), carry)| (chunk, start, Some(carry), func, cancel.clone())).collect::<Vec<_>>();
+        let results = self.map_on_executor(chunks, |_, (mut chunk, start, carry, func, cancel): TryWork<T, E>| -> TryResult<(), E> {
+            let carry = carry.unwrap();
+            for (i, x) in chunk.iter_mut().enumerate() {
+                if cancel.load(Ordering::Relaxed) {
+                    return Err(None)
+                }
+                *x = func(&carry, x).map_err(|error| {
+                    cancel.store(true, Ordering::Relaxed);
+                    Some((start + i, error))
+                })?;
+            }
+            Ok(())
+        })?;
+        first_failure(results)?;
+
+        Ok(data.extract().ok_or(ScanError::BrokenThreadLocking)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prefix_scans;
+    use crate::prefix_scans::try_scan::TryScanError;
+
+    fn checked_add(a: &u8, b: &u8) -> Result<u8, &'static str> {
+        a.checked_add(*b).ok_or("overflow")
+    }
+
+    #[test]
+    fn try_scan_test() {
+        let list = vec![1u8; 200];
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a + b).unwrap();
+        for num_threads in [1, 3, 8] {
+            let mut scanner = prefix_scans::Scanner::new().with_threads(num_threads);
+            assert_eq!(scanner.try_scan(list.clone(), checked_add).unwrap(), baseline);
+            assert_eq!(scanner.try_scan(vec![], checked_add).unwrap(), Vec::<u8>::new());
+        }
+
+        // the prefix first overflows a u8 at index 255, and on one thread, that's exactly where the scan stops
+        let list = vec![1u8; 1000];
+        match prefix_scans::Scanner::new().with_threads(1).try_scan(list.clone(), checked_add) {
+            Err(TryScanError::Operator { index, error }) => assert_eq!((index, error), (255, "overflow")),
+            result => panic!("expected the operator to fail, got {:?}", result)
+        }
+
+        // in parallel, every prefix from there on overflows, so any of them could be the one found, but none before it
+        for num_threads in [2, 3, 8] {
+            match prefix_scans::Scanner::new().with_threads(num_threads).try_scan(list.clone(), checked_add) {
+                Err(TryScanError::Operator { index, error }) => assert!(index >= 255 && error == "overflow"),
+                result => panic!("expected the operator to fail, got {:?}", result)
+            }
+        }
+    }
+}
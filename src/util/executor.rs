@@ -0,0 +1,241 @@
+use std::mem;
+use std::panic;
+use std::sync::mpsc;
+
+use crate::prefix_scans::ScanError;
+use crate::util::thread_pool::{self, ThreadId};
+
+
+/**
+ * A unit of work for an executor to run.
+ */
+pub type Task = Box<dyn FnOnce() + Send>;
+
+/**
+ * Something that can run the scans' work on its threads, in place of the scanner's own `ThreadPool`, such as a pool the
+ * application already has.  An executor only has to run tasks.  `map` and `broadcast` are built on top of `execute`, and
+ * hand it a task for each message, which sends its result back over a channel.
+ */
+pub trait ScanExecutor: Send {
+    /**
+     * How many threads the work is split up for.  These needn't all be distinct threads, this is just how many chunks
+     * the scans split their work into.
+     */
+    fn num_threads(&self) -> usize;
+
+    /**
+     * Runs each task exactly once, on whichever threads, and in whatever order.  It may return before the tasks have
+     * finished.  A task that is dropped without being run, or panics, fails the map it came from.
+     */
+    fn execute(&mut self, tasks: Vec<Task>);
+}
+
+/**
+ * The receiving end of `map_scoped`'s results, which waits for the rest of them when it's dropped, even while unwinding.
+ * Each task holds a sender until it has run or been dropped, so once the channel runs dry, none of them are left.
+ */
+struct WaitForTasks<R>(mpsc::Receiver<(usize, R)>);
+
+impl<R> Drop for WaitForTasks<R> {
+    fn drop(&mut self) {
+        while self.0.recv().is_ok() {}
+    }
+}
+
+impl dyn ScanExecutor {
+    /**
+     * Runs the function on each of the messages, and returns the results in the order of the messages.  Each message's
+     * thread id is its index, and `num_threads`, the same as `ThreadPool::sendall`.
+     */
+    pub fn map<S: 'static + Send, R: 'static + Send>(&mut self, msgs: Vec<S>, function: fn(ThreadId, S) -> R) -> Result<Vec<R>, ScanError> {
+        self.map_scoped(msgs, function)
+    }
+
+    /**
+     * The same as `map`, but the messages and the function can borrow from the caller, like a scoped thread, so that
+     * borrowed data can be worked on in place.  It doesn't return, or unwind, until every task has either run or been
+     * dropped, so none of them can outlive the borrows.  A task that panics is caught, and fails the map with
+     * `ScanError::FailedThreadInGather`, rather than taking down the thread it ran on.
+     */
+    pub fn map_scoped<'env, S: Send + 'env, R: Send + 'env>(&mut self, msgs: Vec<S>, function: impl Fn(ThreadId, S) -> R + Sync + 'env) -> Result<Vec<R>, ScanError> {
+        let (tx, rx) = mpsc::channel();
+        let msg_count = msgs.len();
+        let num_threads = self.num_threads();
+        let function = &function;
+
+        let tasks = msgs.into_iter().enumerate().map(|(i, msg)| {
+            let tx = tx.clone();
+            let task: Box<dyn FnOnce() + Send + '_> = Box::new(move || {
+                if let Ok(result) = panic::catch_unwind(panic::AssertUnwindSafe(|| function((i, num_threads), msg))) {
+                    let _ = tx.send((i, result));
+                }
+            });
+            // SAFETY: the task only differs from a `Task` in its lifetime, and it can't outlive it, since the receiver
+            // below waits for every task's sender to be dropped before this returns, or unwinds
+            unsafe { mem::transmute::<Box<dyn FnOnce() + Send + '_>, Task>(task) }
+        }).collect();
+        // only the tasks hold senders now, so the results run out once every task has finished or been dropped
+        drop(tx);
+        let rx = WaitForTasks(rx);
+        self.execute(tasks);
+
+        let mut results = (0..msg_count).map(|_| None).collect::<Vec<_>>();
+        for (i, result) in rx.0.iter() {
+            results[i] = Some(result);
+        }
+        results.into_iter().collect::<Option<Vec<_>>>().ok_or(ScanError::FailedThreadInGather)
+    }
+
+    /**
+     * Runs the function once per thread, each on its own clone of the message.
+     */
+    pub fn broadcast<S: 'static + Send + Clone, R: 'static + Send>(&mut self, msg: S, function: fn(ThreadId, S) -> R) -> Result<Vec<R>, ScanError> {
+        let msgs = vec![msg; self.num_threads()];
+        self.map(msgs, function)
+    }
+}
+
+/**
+ * The default executor.  A scanner without an executor of its own calls its pool directly instead, which saves boxing
+ * each task, but the pool can also be handed to another scanner as an executor, to share its threads.
+ */
+impl ScanExecutor for thread_pool::ThreadPool {
+    fn num_threads(&self) -> usize {
+        thread_pool::ThreadPool::num_threads(self)
+    }
+
+    fn execute(&mut self, tasks: Vec<Task>) {
+        // a task that fails shows up as a missing result in the map, so there's nothing more to do with it here
+        let _ = self.sendall(tasks, |_, task| task()).gather();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::prefix_scans;
+    use crate::util::executor::{ScanExecutor, Task};
+    use crate::util::thread_pool;
+
+    /**
+     * Runs every task on the calling thread, in order, and counts them.
+     */
+    struct SynchronousExecutor(Arc<AtomicUsize>);
+
+    impl ScanExecutor for SynchronousExecutor {
+        fn num_threads(&self) -> usize {
+            4
+        }
+
+        fn execute(&mut self, tasks: Vec<Task>) {
+            self.0.fetch_add(tasks.len(), Ordering::SeqCst);
+            for task in tasks {
+                task();
+            }
+        }
+    }
+
+    #[test]
+    fn map_test() {
+        let mut executor: Box<dyn ScanExecutor> = Box::new(thread_pool::ThreadPool::new(4));
+        assert_eq!(executor.map((0..10).collect(), |(index, num_threads), x: u64| (index, num_threads, x * x)).unwrap()[3], (3, 4, 9));
+        assert_eq!(executor.broadcast(2, |(index, _), x: usize| index * x).unwrap(), vec![0, 2, 4, 6]);
+
+        let mut executor: Box<dyn ScanExecutor> = Box::new(SynchronousExecutor(Arc::new(AtomicUsize::new(0))));
+        assert_eq!(executor.map(vec![1, 2, 3], |_, x: u64| x + 1).unwrap(), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn map_scoped_test() {
+        let mut executor: Box<dyn ScanExecutor> = Box::new(thread_pool::ThreadPool::new(4));
+        let mut data = (0..100).collect::<Vec<u64>>();
+        let offset = 1000;
+
+        // the messages borrow the data mutably, and the function borrows the offset
+        let sums = executor.map_scoped(data.chunks_mut(10).collect(), |_, chunk: &mut [u64]| {
+            chunk.iter_mut().for_each(|x| *x += offset);
+            chunk.iter().sum::<u64>()
+        }).unwrap();
+        assert_eq!(sums.iter().sum::<u64>(), (0..100).sum::<u64>() + 100 * offset);
+        assert_eq!(data, (1000..1100).collect::<Vec<u64>>());
+
+        // a panic on any thread, the calling thread included, fails the map, and the pool's threads survive it
+        for bad in [0, 3] {
+            let failed = executor.map_scoped(data.chunks(25).collect(), |(index, _), chunk: &[u64]| if index == bad { panic!("task failed") } else { chunk[0] });
+            assert!(matches!(failed, Err(prefix_scans::ScanError::FailedThreadInGather)));
+        }
+        assert_eq!(executor.map_scoped(data.chunks(25).collect(), |_, chunk: &[u64]| chunk[0]).unwrap(), vec![1000, 1025, 1050, 1075]);
+    }
+
+    #[test]
+    fn dropped_task_test() {
+        struct DroppingExecutor;
+        impl ScanExecutor for DroppingExecutor {
+            fn num_threads(&self) -> usize {
+                2
+            }
+
+            fn execute(&mut self, mut tasks: Vec<Task>) {
+                tasks.pop();
+                tasks.into_iter().for_each(|task| task());
+            }
+        }
+
+        let mut executor: Box<dyn ScanExecutor> = Box::new(DroppingExecutor);
+        assert!(matches!(executor.map(vec![1, 2], |_, x: u64| x), Err(prefix_scans::ScanError::FailedThreadInGather)));
+    }
+
+    #[test]
+    fn scanner_executor_test() {
+        let tasks = Arc::new(AtomicUsize::new(0));
+        let mut scanner = prefix_scans::Scanner::new().with_executor(SynchronousExecutor(tasks.clone()));
+        assert_eq!(scanner.num_threads(), 4);
+
+        let list = (0..10000).collect::<Vec<u64>>();
+        let baseline = prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a + b).unwrap();
+        assert_eq!(scanner.divide_and_conquer_scan(list.clone()).unwrap(), baseline);
+        assert_eq!(scanner.hillis_steel_scan(list.clone()).unwrap(), baseline);
+        assert_eq!(scanner.blelloch_scan(list.clone()).unwrap()[1..], baseline[..baseline.len() - 1]);
+        assert!(tasks.load(Ordering::SeqCst) > 0);
+
+        // a clone shares the executor
+        let before = tasks.load(Ordering::SeqCst);
+        assert_eq!(scanner.clone().divide_and_conquer_scan(list.clone()).unwrap(), baseline);
+        assert!(tasks.load(Ordering::SeqCst) > before);
+
+        // the paths over borrowed data run on the executor too, with no threads of their own
+        let on_executor = |scanner: &mut prefix_scans::Scanner, path: &dyn Fn(&mut prefix_scans::Scanner)| {
+            let before = tasks.load(Ordering::SeqCst);
+            path(scanner);
+            tasks.load(Ordering::SeqCst) > before
+        };
+        let sum = list.iter().sum::<u64>();
+        assert!(on_executor(&mut scanner, &|scanner| assert_eq!(scanner.parallel_quicksum_simd(&list).unwrap(), sum)));
+        assert!(on_executor(&mut scanner, &|scanner| assert_eq!(scanner.parallel_reduce_sum(&list).unwrap(), sum)));
+        assert!(on_executor(&mut scanner, &|scanner| assert_eq!(scanner.parallel_reduce(&list, 0, |a, b| a + b).unwrap(), sum)));
+        assert!(on_executor(&mut scanner, &|scanner| assert_eq!(scanner.parallel_reduce_generic(&list, |a, b| a + b).unwrap(), sum)));
+        assert!(on_executor(&mut scanner, &|scanner| assert_eq!(scanner.scan_with_runs(list.clone()).unwrap().0, baseline)));
+        assert!(on_executor(&mut scanner, &|scanner| {
+            let mut data = list.clone();
+            let (front, back) = data.split_at_mut(3000);
+            scanner.scan_chunks(&mut [front, back]).unwrap();
+            assert_eq!(data, baseline);
+        }));
+        assert!(on_executor(&mut scanner, &|scanner| assert_eq!(scanner.block_sums(&list).unwrap().1.iter().sum::<u64>(), sum)));
+        // the conversions only go parallel from `parallel_length` on
+        scanner.set_parallel_length(0);
+        let exclusive = std::iter::once(0).chain(baseline[..baseline.len() - 1].iter().copied()).collect::<Vec<_>>();
+        assert!(on_executor(&mut scanner, &|scanner| {
+            let mut data = baseline.clone();
+            scanner.inclusive_to_exclusive(&mut data, 0).unwrap();
+            assert_eq!(data, exclusive);
+        }));
+        assert!(on_executor(&mut scanner, &|scanner| {
+            let mut data = exclusive.clone();
+            scanner.exclusive_to_inclusive(&mut data, &list).unwrap();
+            assert_eq!(data, baseline);
+        }));
+    }
+}
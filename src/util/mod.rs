@@ -1,3 +1,5 @@
 pub mod thread_pool;
 pub mod split_vector;
-pub mod ranged_vector;
\ No newline at end of file
+pub mod ranged_vector;
+pub mod simd;
+pub mod executor;
\ No newline at end of file
@@ -1,5 +1,6 @@
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Range<T> {
     start: usize,
     end: usize,
@@ -23,9 +24,17 @@ impl<T> Range<T> {
     pub fn end(&self) -> usize {
         self.end
     }
+
+    /**
+     * Whether the point is within [start..end).
+     */
+    pub fn contains(&self, point: usize) -> bool {
+        self.start <= point && point < self.end
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RangedVector<T> {
     ranges: Vec<Range<T>>
 }
@@ -37,14 +46,27 @@ impl<T> RangedVector<T> {
         }
     }
 
+    /**
+     * Finds the range containing the point.  The ranges are sorted by their ends, so this is a binary search for the
+     * first range that ends after the point.
+     */
     pub fn get(&self, point: usize) -> Option<&Range<T>> {
-        for range in self.ranges.iter() {
-            if point < range.end {
-                return Some(range)
-            }
-        }
+        self.ranges.get(self.ranges.partition_point(|range| range.end <= point))
+    }
 
-        return None
+    /**
+     * Iterates over the ranges in order.
+     */
+    pub fn iter(&self) -> impl Iterator<Item = &Range<T>> {
+        self.ranges.iter()
+    }
+
+    /**
+     * Iterates over the ranges overlapping [start..end), in order, starting from the one containing start.
+     */
+    pub fn overlapping(&self, start: usize, end: usize) -> impl Iterator<Item = &Range<T>> {
+        let first = self.ranges.partition_point(|range| range.end <= start);
+        self.ranges[first..].iter().take_while(move |range| range.start < end)
     }
 
     pub fn get_range(&self, index: usize) -> Option<&Range<T>> {
@@ -62,4 +84,57 @@ impl<T> RangedVector<T> {
             None
         }
     }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::util::ranged_vector;
+
+    #[test]
+    fn get_many_ranges_test() {
+        // ranges of varying widths, [0..1), [1..3), [3..6) ...
+        let points = (0..5001).map(|i| i * (i + 1) / 2).collect::<Vec<usize>>();
+        let ranges = ranged_vector::RangedVector::new(points.clone(), (0..5000).collect());
+
+        for (i, range) in points.windows(2).enumerate() {
+            for point in [range[0], (range[0] + range[1]) / 2, range[1] - 1] {
+                assert_eq!(*ranges.get(point).unwrap().value(), i);
+            }
+        }
+        assert!(ranges.get(*points.last().unwrap()).is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_test() {
+        let ranges = ranged_vector::RangedVector::new(vec![0, 4, 7, 10], vec![1u64, 2, 3]);
+        let json = serde_json::to_string(&ranges).unwrap();
+        let deserialized = serde_json::from_str::<ranged_vector::RangedVector<u64>>(&json).unwrap();
+        assert_eq!(deserialized, ranges);
+        assert_eq!(*deserialized.get(5).unwrap().value(), 2);
+    }
+
+    #[test]
+    fn iter_test() {
+        let ranges = ranged_vector::RangedVector::new(vec![0, 4, 7, 10], vec!['a', 'b', 'c']);
+        let collected = ranges.iter().map(|range| (range.start(), range.end(), *range.value())).collect::<Vec<_>>();
+        assert_eq!(collected, vec![(0, 4, 'a'), (4, 7, 'b'), (7, 10, 'c')]);
+
+        for point in 0..10 {
+            assert_eq!(ranges.iter().filter(|range| range.contains(point)).count(), 1);
+            assert!(ranges.get(point).unwrap().contains(point));
+        }
+        assert!(!ranges.iter().any(|range| range.contains(10)));
+    }
+
+    #[test]
+    fn overlapping_test() {
+        let ranges = ranged_vector::RangedVector::new(vec![0, 4, 7, 10], vec!['a', 'b', 'c']);
+        let overlapping = |start, end| ranges.overlapping(start, end).map(|range| *range.value()).collect::<String>();
+        assert_eq!(overlapping(0, 10), "abc");
+        assert_eq!(overlapping(4, 7), "b");
+        assert_eq!(overlapping(3, 8), "abc");
+        assert_eq!(overlapping(6, 7), "b");
+        assert_eq!(overlapping(10, 12), "");
+    }
 }
\ No newline at end of file
@@ -0,0 +1,257 @@
+use std::ops;
+
+
+/**
+ * The operations the simd kernels in `helper_functions` need from a vector of lanes.
+ */
+pub trait SimdVector: Copy + ops::Add<Output = Self> {
+    type Element: Copy + Default;
+    const LANES: usize;
+
+    /**
+     * Adds two elements the same way the lanes are added, so integers wrap on overflow.  Used for the elements left
+     * over at the end that don't fill a whole vector.
+     */
+    fn add_elements(a: Self::Element, b: Self::Element) -> Self::Element;
+
+    fn splat(value: Self::Element) -> Self;
+    /**
+     * Loads the first `LANES` elements of the slice.  Panics if the slice is shorter than that.
+     */
+    fn from_slice(slice: &[Self::Element]) -> Self;
+    /**
+     * Loads up to `LANES` elements of the slice, filling any lanes past its end with zeros.  This lets the elements left
+     * over at the end be added as one more vector, rather than one at a time.
+     */
+    fn load_or_default(slice: &[Self::Element]) -> Self;
+    fn write_to_slice(self, slice: &mut [Self::Element]);
+    fn extract(self, index: usize) -> Self::Element;
+    fn replace(self, index: usize, value: Self::Element) -> Self;
+    /**
+     * Moves every lane `distance` lanes to the right, shifting in zeros on the left:
+     *  [a, b, c, d].shift_lanes_right(1) == [0, a, b, c]
+     */
+    fn shift_lanes_right(self, distance: usize) -> Self;
+    /**
+     * Adds up all of the lanes.  Integer lanes wrap on overflow.
+     */
+    fn sum(self) -> Self::Element;
+}
+
+/*
+ * Without the `portable_simd` feature, each vector is a plain array, and each operation a loop over the lanes.  The lane
+ * counts are fixed, so llvm unrolls and vectorizes these loops by itself, and the crate builds on stable.  Integer lanes
+ * wrap on overflow, the same as `core::simd`.
+ */
+#[cfg(not(feature = "portable_simd"))]
+macro_rules! simd_vector {
+    ($name:ident, $elem:ty, $lanes:expr, $add:expr) => {
+        #[allow(non_camel_case_types)]
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        pub struct $name([$elem; $lanes]);
+
+        impl ops::Add for $name {
+            type Output = Self;
+
+            #[inline(always)]
+            fn add(mut self, other: Self) -> Self {
+                for (lane, other) in self.0.iter_mut().zip(other.0.iter()) {
+                    *lane = ($add)(*lane, *other);
+                }
+                self
+            }
+        }
+
+        impl SimdVector for $name {
+            type Element = $elem;
+            const LANES: usize = $lanes;
+
+            #[inline(always)]
+            fn add_elements(a: $elem, b: $elem) -> $elem {
+                ($add)(a, b)
+            }
+
+            #[inline(always)]
+            fn splat(value: $elem) -> Self {
+                Self([value; $lanes])
+            }
+
+            #[inline(always)]
+            fn from_slice(slice: &[$elem]) -> Self {
+                let mut lanes = [<$elem>::default(); $lanes];
+                lanes.copy_from_slice(&slice[..$lanes]);
+                Self(lanes)
+            }
+
+            #[inline(always)]
+            fn load_or_default(slice: &[$elem]) -> Self {
+                let len = std::cmp::min(slice.len(), $lanes);
+                let mut lanes = [<$elem>::default(); $lanes];
+                lanes[..len].copy_from_slice(&slice[..len]);
+                Self(lanes)
+            }
+
+            #[inline(always)]
+            fn write_to_slice(self, slice: &mut [$elem]) {
+                slice[..$lanes].copy_from_slice(&self.0);
+            }
+
+            #[inline(always)]
+            fn extract(self, index: usize) -> $elem {
+                self.0[index]
+            }
+
+            #[inline(always)]
+            fn replace(mut self, index: usize, value: $elem) -> Self {
+                self.0[index] = value;
+                self
+            }
+
+            #[inline(always)]
+            fn shift_lanes_right(self, distance: usize) -> Self {
+                let mut lanes = [<$elem>::default(); $lanes];
+                lanes[distance..].copy_from_slice(&self.0[..$lanes - distance]);
+                Self(lanes)
+            }
+
+            #[inline(always)]
+            fn sum(self) -> $elem {
+                self.0.iter().fold(<$elem>::default(), |acc, lane| ($add)(acc, *lane))
+            }
+        }
+    };
+}
+
+/*
+ * With the `portable_simd` feature, each vector wraps a `core::simd::Simd`.  Its shifts take the distance as a const
+ * parameter, so the kernels' power of two distances are matched on, which folds away once the kernel is inlined.
+ */
+#[cfg(feature = "portable_simd")]
+macro_rules! simd_vector {
+    ($name:ident, $elem:ty, $lanes:expr, $add:expr) => {
+        #[allow(non_camel_case_types)]
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        pub struct $name(core::simd::Simd<$elem, $lanes>);
+
+        impl ops::Add for $name {
+            type Output = Self;
+
+            #[inline(always)]
+            fn add(self, other: Self) -> Self {
+                Self(self.0 + other.0)
+            }
+        }
+
+        impl SimdVector for $name {
+            type Element = $elem;
+            const LANES: usize = $lanes;
+
+            #[inline(always)]
+            fn add_elements(a: $elem, b: $elem) -> $elem {
+                ($add)(a, b)
+            }
+
+            #[inline(always)]
+            fn splat(value: $elem) -> Self {
+                Self(core::simd::Simd::splat(value))
+            }
+
+            #[inline(always)]
+            fn from_slice(slice: &[$elem]) -> Self {
+                Self(core::simd::Simd::from_slice(slice))
+            }
+
+            #[inline(always)]
+            fn load_or_default(slice: &[$elem]) -> Self {
+                Self(core::simd::Simd::load_or_default(slice))
+            }
+
+            #[inline(always)]
+            fn write_to_slice(self, slice: &mut [$elem]) {
+                self.0.copy_to_slice(slice)
+            }
+
+            #[inline(always)]
+            fn extract(self, index: usize) -> $elem {
+                self.0[index]
+            }
+
+            #[inline(always)]
+            fn replace(mut self, index: usize, value: $elem) -> Self {
+                self.0[index] = value;
+                self
+            }
+
+            #[inline(always)]
+            fn shift_lanes_right(self, distance: usize) -> Self {
+                let zero = <$elem>::default();
+                Self(match distance {
+                    0 => self.0,
+                    1 => self.0.shift_elements_right::<1>(zero),
+                    2 => self.0.shift_elements_right::<2>(zero),
+                    4 => self.0.shift_elements_right::<4>(zero),
+                    8 => self.0.shift_elements_right::<8>(zero),
+                    _ => {
+                        let mut lanes = [zero; $lanes];
+                        lanes[distance..].copy_from_slice(&self.0.as_array()[..$lanes - distance]);
+                        core::simd::Simd::from_array(lanes)
+                    }
+                })
+            }
+
+            #[inline(always)]
+            fn sum(self) -> $elem {
+                use core::simd::num::*;
+                self.0.reduce_sum()
+            }
+        }
+    };
+}
+
+simd_vector!(u64x4, u64, 4, u64::wrapping_add);
+simd_vector!(u64x8, u64, 8, u64::wrapping_add);
+simd_vector!(u64x16, u64, 16, u64::wrapping_add);
+simd_vector!(f64x8, f64, 8, |a: f64, b: f64| a + b);
+simd_vector!(u32x16, u32, 16, u32::wrapping_add);
+simd_vector!(i32x16, i32, 16, i32::wrapping_add);
+simd_vector!(i64x8, i64, 8, i64::wrapping_add);
+
+#[cfg(test)]
+mod test {
+    use crate::util::simd::{self, SimdVector};
+
+    #[test]
+    fn shift_lanes_right_test() {
+        let lanes = (1..9).collect::<Vec<u64>>();
+        let mut shifted = vec![0; 8];
+        simd::u64x8::from_slice(&lanes).shift_lanes_right(3).write_to_slice(&mut shifted);
+        assert_eq!(shifted, vec![0, 0, 0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn lane_widths_test() {
+        let lanes = (1..17).collect::<Vec<u64>>();
+        let mut shifted = vec![0; 16];
+        simd::u64x16::from_slice(&lanes).shift_lanes_right(8).write_to_slice(&mut shifted);
+        assert_eq!(shifted[..9], [0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(simd::u64x16::from_slice(&lanes).sum(), 136);
+
+        let mut shifted = vec![0; 4];
+        simd::u64x4::from_slice(&lanes).shift_lanes_right(2).write_to_slice(&mut shifted);
+        assert_eq!(shifted, vec![0, 0, 1, 2]);
+    }
+
+    #[test]
+    fn load_or_default_test() {
+        let mut loaded = vec![0; 8];
+        simd::u64x8::load_or_default(&[1, 2, 3]).write_to_slice(&mut loaded);
+        assert_eq!(loaded, vec![1, 2, 3, 0, 0, 0, 0, 0]);
+        assert_eq!(simd::u64x8::load_or_default(&(1..20).collect::<Vec<_>>()), simd::u64x8::from_slice(&(1..9).collect::<Vec<_>>()));
+        assert_eq!(simd::u64x8::load_or_default(&[]), simd::u64x8::splat(0));
+    }
+
+    #[test]
+    fn sum_wraps_test() {
+        assert_eq!(simd::u64x8::splat(u64::MAX).replace(0, 8).sum(), 1);
+    }
+}
@@ -3,48 +3,126 @@ use std::slice;
 use std::ops;
 use std::default::Default;
 
+use crate::prefix_scans::helper_functions;
 
+
+/**
+ * The reasons a `SplitVector` can fail to be chunked.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChunkError {
+    // the offset at index `at` is not greater than the one before it
+    NotAscending { at: usize },
+    // the last offset is past the end of the vector
+    OutOfRange,
+    // the offsets don't start at 0 and end at the end of the data, so some of it wouldn't be in any chunk
+    Incomplete,
+    // chunks from a previous call are still alive, so the vector can't be borrowed mutably
+    Aliased
+}
+
+/**
+ * A chunk of a `SplitVector` holds a reference to the vector to keep it alive.  Chunks of a `SplitSlice` borrow the
+ * slice instead, so they have nothing to hold.
+ */
 pub struct SplitVectorChunk<'a, T> {
-    _main_memory: Arc<Vec<T>>,
+    _main_memory: Option<Arc<Vec<T>>>,
     chunk: &'a mut [T]
 }
 
-impl<'a, T> SplitVectorChunk<'a, T> {
-    pub fn len(&self) -> usize {
-        self.chunk.len()
-    }
+/**
+ * A chunk derefs to its slice, so indexing and all of the slice methods work on it directly.
+ */
+impl<'a, T> ops::Deref for SplitVectorChunk<'a, T> {
+    type Target = [T];
 
-    pub fn last(&self) -> Option<&T> {
-        self.chunk.last()
+    fn deref(&self) -> &[T] {
+        self.chunk
     }
+}
 
-    pub fn last_mut(&mut self) -> Option<&mut T> {
-        self.chunk.last_mut()
+impl<'a, T> ops::DerefMut for SplitVectorChunk<'a, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.chunk
     }
+}
 
-    pub fn raw_chunk_mut(&mut self)  -> &mut [T] {
-        self.chunk
+impl<'a, T> SplitVectorChunk<'a, T> {
+    /**
+     * Splits the chunk in two at `mid`, like `slice::split_at_mut`, so that the halves can be handed to different tasks.
+     * Both halves hold a reference to the vector, so it stays alive, and can't be extracted, until both are dropped.
+     * Panics if `mid` is past the end of the chunk.
+     */
+    pub fn split_at_mut(self, mid: usize) -> (Self, Self) {
+        let (left, right) = self.chunk.split_at_mut(mid);
+        (SplitVectorChunk { _main_memory: self._main_memory.clone(), chunk: left }, SplitVectorChunk { _main_memory: self._main_memory, chunk: right })
     }
 }
 
-impl<'a, T, I> ops::Index<I> for SplitVectorChunk<'a, T> 
-where I: std::slice::SliceIndex<[T]> {
-    type Output = I::Output;
+impl<'s, 'a, T> IntoIterator for &'s mut SplitVectorChunk<'a, T> {
+    type Item = &'s mut T;
+    type IntoIter = slice::IterMut<'s, T>;
 
-    fn index(&self, index: I) -> &Self::Output {
-        &self.chunk[index]
+    fn into_iter(self) -> Self::IntoIter {
+        self.chunk.iter_mut()
     }
-} 
+}
 
-impl<'a, T, I> ops::IndexMut<I> for SplitVectorChunk<'a, T> 
-where I: std::slice::SliceIndex<[T]> {
-    fn index_mut(&mut self, index: I) -> &mut Self::Output {
-        &mut self.chunk[index]
+/**
+ * The checks shared by both kinds of chunking.  Strictly ascending offsets within range partition the vector into
+ * disjoint slices, so no two chunks can alias the same element.
+ */
+pub(crate) fn validate_offsets(offsets: &[usize], len: usize) -> Result<(), ChunkError> {
+    if let Some(at) = (1..offsets.len()).find(|&i| offsets[i - 1] >= offsets[i]) {
+        return Err(ChunkError::NotAscending { at })
     }
-} 
+    if offsets.last().is_some_and(|&last| last > len) {
+        return Err(ChunkError::OutOfRange)
+    }
+
+    Ok(())
+}
 
 pub struct SplitVector<T>(Arc<Vec<T>>);
 
+/**
+ * Chunks a slice that's already owned elsewhere, without moving it into an `Arc`.  The chunks borrow the slice, so the
+ * borrow checker rules out aliasing instead of the reference count, and they can't outlive it.  That means they can only
+ * be sent to scoped threads, not the thread pool.
+ */
+pub struct SplitSlice<'a, T>(&'a mut [T]);
+
+impl<'a, T> SplitSlice<'a, T> {
+    /**
+     * The same as `SplitVector::chunk`, except that chunks can't be alive from a previous call, so it never fails with
+     * `ChunkError::Aliased`.
+     */
+    pub fn chunk(&mut self, offsets: &[usize]) -> Result<Vec<SplitVectorChunk<'_, T>>, ChunkError> {
+        validate_offsets(offsets, self.0.len())?;
+
+        let mut rest = match offsets.first() {
+            Some(&first) => &mut self.0[first..],
+            None => return Ok(Vec::new())
+        };
+        let mut chunks = Vec::with_capacity(offsets.len() - 1);
+        for pair in offsets.windows(2) {
+            let (chunk, remaining) = std::mem::take(&mut rest).split_at_mut(pair[1] - pair[0]);
+            chunks.push(SplitVectorChunk { _main_memory: None, chunk });
+            rest = remaining;
+        }
+
+        Ok(chunks)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
 impl<T: Default> SplitVector<T> {
     pub fn with_size(size: usize) -> Self {
         Self(Arc::new((0..size).map(|_| T::default()).collect::<Vec<_>>()))
@@ -54,36 +132,43 @@ impl<T: Default> SplitVector<T> {
         Self(Arc::new(vec))
     }
 
+    /**
+     * Chunks a borrowed slice in place, see `SplitSlice`.
+     */
+    pub fn from_mut_slice(slice: &mut [T]) -> SplitSlice<'_, T> {
+        SplitSlice(slice)
+    }
+
     /**
      * Given an array of offsets, splits its contained vector into chunks based on those offsets.
      * For example, these offsets:
      *      [ a, b, c, d ]
      * Will lead to three chunks, [a..b], [b..c] and [c..d]
-     * The offsets must be strictly increasing and within the range of this vector.
+     * The offsets must be strictly increasing and within the range of this vector.  Fewer than two offsets make no
+     * chunks at all.
      */
-    pub fn chunk<'a, 'b>(&'a mut self, offsets: &[usize]) -> Option<Vec<SplitVectorChunk<'b, T>>> {
-        // ensure strictly ascending offsets within range
-        if *offsets.last()? > self.0.len() {
-            return None
-        }
-
-        let vector_start = Arc::get_mut(&mut self.0)?.as_mut_ptr();
-        let mut chunks = Vec::with_capacity(offsets.len() + 1);
+    pub fn chunk<'b>(&mut self, offsets: &[usize]) -> Result<Vec<SplitVectorChunk<'b, T>>, ChunkError> {
+        /*
+         * The chunks are made from raw pointers, so validating the offsets is what makes this sound.  It happens before
+         * any chunks are made, so a bad offset never leaks an overlapping chunk to another thread.
+         */
+        validate_offsets(offsets, self.0.len())?;
 
-        for i in 0..(offsets.len() - 1) {
-            if offsets[i] >= offsets[i + 1] {
-                return None
-            }
+        let vector_start = Arc::get_mut(&mut self.0).ok_or(ChunkError::Aliased)?.as_mut_ptr();
+        let mut chunks = Vec::with_capacity(offsets.len().saturating_sub(1));
 
+        for i in 0..offsets.len().saturating_sub(1) {
+            // SAFETY: [offsets[i]..offsets[i + 1]) is in range and disjoint from every other chunk, and each chunk holds a
+            // reference to the vector, which keeps it alive and blocks `get_mut` until every chunk is dropped
             unsafe {
                 chunks.push(SplitVectorChunk {
-                    _main_memory: self.0.clone(),
+                    _main_memory: Some(self.0.clone()),
                     chunk: slice::from_raw_parts_mut(vector_start.add(offsets[i]), offsets[i + 1] - offsets[i])
                 })
             }
         }
 
-        Some(chunks)
+        Ok(chunks)
     }
 
     /**
@@ -93,21 +178,60 @@ impl<T: Default> SplitVector<T> {
      * Using chunk will return these chunks: [ a, b, c, d ]
      * Using chunk_all will return these chunks: [ a, b, c, d ], [ e, f, g ]
      */
-    pub fn chunk_all<'a, 'b>(&'a mut self, mut offsets: Vec<usize>) -> Option<Vec<SplitVectorChunk<'b, T>>> {
+    pub fn chunk_all<'b>(&mut self, mut offsets: Vec<usize>) -> Result<Vec<SplitVectorChunk<'b, T>>, ChunkError> {
         offsets.push(self.0.len());
         self.chunk(&offsets[..])
     }
 
+    /**
+     * Chunks the vector, and hands the chunks to the closure.  The closure has to work for any lifetime of the chunks, so
+     * it can't return them, or send them anywhere that needs them to be `'static`, like the thread pool.  Scoped threads
+     * are fine, since they're joined before the closure returns.  So unless the closure leaks them on purpose, with
+     * `mem::forget`, every chunk is dropped by the time this returns, and `extract` is sure to succeed.
+     */
+    pub fn with_chunks<R>(&mut self, offsets: &[usize], func: impl for<'c> FnOnce(Vec<SplitVectorChunk<'c, T>>) -> R) -> Result<R, ChunkError> {
+        Ok(func(self.chunk(offsets)?))
+    }
+
+    /**
+     * Splits the whole vector into `num_chunks` chunks as evenly as possible, using the offsets from
+     * `helper_functions::chunk_ranges`.  If there are fewer elements than chunks, there is one chunk per element.
+     */
+    pub fn chunk_even<'b>(&mut self, num_chunks: usize) -> Result<Vec<SplitVectorChunk<'b, T>>, ChunkError> {
+        self.chunk(&helper_functions::chunk_ranges(self.0.len(), num_chunks))
+    }
+
     pub fn len(&self) -> usize {
         self.0.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     /**
      * Attempt to extract the inner vector, assuming no other references are held to it.  The vector
      * is returned and replaced with an empty vector.
      */
     pub fn extract(&mut self) -> Option<Vec<T>> {
-        Some(std::mem::replace(Arc::get_mut(&mut self.0)?, Vec::new()))
+        Some(std::mem::take(Arc::get_mut(&mut self.0)?))
+    }
+
+    /**
+     * The same as `extract`, but on failure returns how many references are still held to the vector, counting the
+     * `SplitVector`'s own.  One less than that is the number of chunks that haven't been dropped yet.
+     */
+    pub fn extract_or_refcount(&mut self) -> Result<Vec<T>, usize> {
+        let ref_count = self.ref_count();
+        self.extract().ok_or(ref_count)
+    }
+
+    /**
+     * Swaps the inner vector with another, assuming no other references are held to it.  Neither vector is reallocated.
+     */
+    pub fn swap_vec(&mut self, other: &mut Vec<T>) -> Option<()> {
+        std::mem::swap(Arc::get_mut(&mut self.0)?, other);
+        Some(())
     }
 
     pub fn take_vec(self) -> Option<Vec<T>> {
@@ -137,7 +261,7 @@ mod tests {
 
         println!("pre chunked ref count == {:?}", sv.ref_count());
         {
-            let mut chunks = sv.chunk(&vec![0, 2, 4, 5]).unwrap();
+            let mut chunks = sv.chunk(&[0, 2, 4, 5]).unwrap();
             println!("post chunked ref count == {:?}", sv.ref_count());
             for chunk in chunks.iter_mut() {
                 for i in 0..chunk.chunk.len() {
@@ -148,4 +272,130 @@ mod tests {
 
         println!("modified vector: {:?}", sv.view_mut());
     }
+
+    #[test]
+    fn overlapping_chunks_test() {
+        let mut sv = split_vector::SplitVector::<u64>::with_size(10);
+        // [0..6) and [6..4) would overlap [4..10), and must be rejected before any chunks are handed out
+        assert_eq!(sv.chunk(&[0, 6, 4, 10]).err(), Some(split_vector::ChunkError::NotAscending { at: 2 }));
+        assert_eq!(sv.chunk_all(vec![0, 8, 2]).err(), Some(split_vector::ChunkError::NotAscending { at: 2 }));
+        assert_eq!(sv.ref_count(), 1);
+
+        // chunking again while chunks are still alive would alias them too
+        let chunks = sv.chunk(&[0, 10]).unwrap();
+        assert_eq!(sv.chunk(&[0, 10]).err(), Some(split_vector::ChunkError::Aliased));
+        drop(chunks);
+    }
+
+    #[test]
+    fn chunk_iter_mut_test() {
+        let mut sv = split_vector::SplitVector::with_vec((0..10).collect::<Vec<u64>>());
+        for mut chunk in sv.chunk(&[0, 3, 10]).unwrap() {
+            let first = chunk[0];
+            chunk.iter_mut().for_each(|x| *x -= first);
+            for x in &mut chunk {
+                *x *= 2;
+            }
+        }
+        assert_eq!(sv.extract().unwrap(), vec![0, 2, 4, 0, 2, 4, 6, 8, 10, 12]);
+    }
+
+    #[test]
+    fn with_chunks_test() {
+        let mut sv = split_vector::SplitVector::with_vec((0..10).collect::<Vec<u64>>());
+        let doubled = sv.with_chunks(&[0, 3, 10], |chunks| {
+            std::thread::scope(|scope| {
+                for mut chunk in chunks {
+                    scope.spawn(move || chunk.iter_mut().for_each(|x| *x *= 2));
+                }
+            });
+        });
+        assert!(doubled.is_ok());
+        assert_eq!(sv.ref_count(), 1);
+
+        let total = sv.with_chunks(&[0, 5, 10], |chunks| chunks.iter().map(|chunk| chunk.iter().sum::<u64>()).sum::<u64>());
+        assert_eq!(total, Ok(90));
+        assert_eq!(sv.with_chunks(&[5, 0], |chunks| chunks.len()), Err(split_vector::ChunkError::NotAscending { at: 1 }));
+        assert_eq!(sv.extract().unwrap(), (0..10).map(|x| x * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn extract_or_refcount_test() {
+        let mut sv = split_vector::SplitVector::with_vec((0..10).collect::<Vec<u64>>());
+        let mut chunks = sv.chunk(&[0, 3, 6, 10]).unwrap();
+        assert_eq!(sv.extract_or_refcount().err(), Some(4));
+
+        // holding on to one chunk still blocks the extract
+        let leaked = chunks.pop().unwrap();
+        drop(chunks);
+        assert_eq!(sv.extract_or_refcount().err(), Some(2));
+
+        drop(leaked);
+        assert_eq!(sv.extract_or_refcount().unwrap(), (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn split_at_mut_test() {
+        let mut sv = split_vector::SplitVector::with_vec((0..10).collect::<Vec<u64>>());
+        let chunk = sv.chunk(&[2, 9]).unwrap().pop().unwrap();
+        let (mut left, right) = chunk.split_at_mut(3);
+        assert_eq!((&left[..], &right[..]), (&[2, 3, 4][..], &[5, 6, 7, 8][..]));
+        assert_eq!(sv.ref_count(), 3);
+
+        // the halves can be split again, and all of them mutated from different threads
+        let (mut middle, mut last) = right.split_at_mut(4);
+        assert!(last.is_empty());
+        std::thread::scope(|scope| {
+            scope.spawn(|| left.iter_mut().for_each(|x| *x *= 10));
+            scope.spawn(|| middle.iter_mut().for_each(|x| *x += 100));
+            scope.spawn(|| last.iter_mut().for_each(|x| *x = 0));
+        });
+        assert!(sv.extract().is_none());
+
+        drop((left, middle, last));
+        assert_eq!(sv.extract().unwrap(), vec![0, 1, 20, 30, 40, 105, 106, 107, 108, 9]);
+    }
+
+    #[test]
+    fn chunk_even_test() {
+        for (len, num_chunks) in [(10, 3), (7, 7), (3, 8), (100, 1), (0, 4)] {
+            let mut sv = split_vector::SplitVector::with_vec((0..len).collect::<Vec<u64>>());
+            let chunks = sv.chunk_even(num_chunks).unwrap();
+            assert_eq!(chunks.len(), std::cmp::min(len as usize, num_chunks));
+            // joined back together, the chunks are the whole vector, in order, with nothing missing or repeated
+            assert_eq!(chunks.iter().flat_map(|chunk| chunk.iter().copied()).collect::<Vec<_>>(), (0..len).collect::<Vec<_>>());
+            assert!(chunks.iter().all(|chunk| chunk.len() == (len as usize).div_ceil(num_chunks) || chunk.len() == len as usize / num_chunks));
+        }
+    }
+
+    #[test]
+    fn from_mut_slice_test() {
+        let mut array = [1u64, 2, 3, 4, 5, 6, 7];
+        let mut split = split_vector::SplitVector::from_mut_slice(&mut array[1..]);
+        assert_eq!(split.len(), 6);
+        assert_eq!(split.chunk(&[0, 4, 4]).err(), Some(split_vector::ChunkError::NotAscending { at: 2 }));
+        assert_eq!(split.chunk(&[0, 7]).err(), Some(split_vector::ChunkError::OutOfRange));
+        assert_eq!(split.chunk(&[]).unwrap().len(), 0);
+
+        // the chunks can be sent to scoped threads, which got nothing else from the slice
+        std::thread::scope(|scope| {
+            for (i, mut chunk) in split.chunk(&[1, 2, 5, 6]).unwrap().into_iter().enumerate() {
+                scope.spawn(move || chunk.iter_mut().for_each(|x| *x *= 10 * (i as u64 + 1)));
+            }
+        });
+        assert_eq!(array, [1, 2, 30, 80, 100, 120, 210]);
+    }
+
+    #[test]
+    fn chunk_error_test() {
+        let mut sv = split_vector::SplitVector::<u64>::with_size(10);
+        assert_eq!(sv.chunk(&[0, 4, 4, 10]).err(), Some(split_vector::ChunkError::NotAscending { at: 2 }));
+        assert_eq!(sv.chunk(&[0, 5, 11]).err(), Some(split_vector::ChunkError::OutOfRange));
+
+        let chunks = sv.chunk(&[0, 5, 10]).unwrap();
+        assert_eq!(sv.chunk(&[0, 10]).err(), Some(split_vector::ChunkError::Aliased));
+        drop(chunks);
+        assert_eq!(sv.chunk(&[0, 10]).unwrap().len(), 1);
+        assert_eq!(sv.chunk(&[]).unwrap().len(), 0);
+    }
 }
\ No newline at end of file
@@ -1,12 +1,21 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+#[cfg(feature = "rayon")]
+use std::panic;
 use std::thread;
-use std::sync::mpsc;
+use std::time;
+use std::sync::{mpsc, Arc, Mutex};
 use std::ops::{Index, IndexMut};
 
+use crate::prefix_scans::ScanError;
+use crate::util::executor;
+
 
 /**
- * A thread id is a tuple, (thread's individual index, total number of threads)
+ * A thread id is a tuple, (message's index, total number of threads).  With one message per thread, the message's
+ * index is also the thread's individual index.
  */
-type ThreadId = (usize, usize);
+pub type ThreadId = (usize, usize);
 /**
  * A specific function signature that takes in a thread id and argument, and produces some return value
  */
@@ -16,11 +25,66 @@ type ThreadFunction<S, R> = fn(ThreadId, S) -> R;
  */
 type ThreadSendResultChannel<R> = mpsc::Sender<(ThreadId, R)>;
 type ThreadReceiveResultChannel<R> = mpsc::Receiver<(ThreadId, R)>;
+/**
+ * The result channel `map` keeps around between calls, where a missing result comes through as None.
+ */
+type ResultChannel<R> = (ThreadSendResultChannel<Option<R>>, ThreadReceiveResultChannel<Option<R>>);
+
+/**
+ * The ways gathering the results of a `MassReceiver` with a timeout can fail.
+ */
+#[derive(Debug, PartialEq, Eq)]
+pub enum GatherError {
+    // every thread's sender was dropped before all of the results arrived
+    Disconnected,
+    // the thread with this index sent more than one result
+    Duplicate { index: usize },
+    // a result came back with an index past the number of messages sent
+    OutOfRange { index: usize },
+    // the threads with these indices still hadn't reported when the timeout ran out
+    TimedOut { missing: Vec<usize> }
+}
+
+/**
+ * A remote thread's channel was closed, because the thread died, say by panicking in an earlier message.  The message
+ * never reaches it.
+ */
+#[derive(Debug, PartialEq, Eq)]
+pub struct ThreadDied;
 
 trait Callable {
     fn call(self: Box<Self>);
 }
 
+/**
+ * The queue shared by all of the threads in work stealing mode, which idle threads pull work from.
+ */
+type WorkQueue = Arc<crossbeam_deque::Injector<Box<dyn Callable + Send>>>;
+
+/**
+ * Runs work from the queue until it's empty.
+ */
+fn drain(queue: &WorkQueue) {
+    loop {
+        match queue.steal() {
+            crossbeam_deque::Steal::Success(work) => work.call(),
+            crossbeam_deque::Steal::Retry => continue,
+            crossbeam_deque::Steal::Empty => break
+        }
+    }
+}
+
+/**
+ * Sent to a remote thread to wake it up, and have it help drain the queue.
+ */
+struct StealWork(WorkQueue);
+
+impl Callable for StealWork {
+    fn call(self: Box<Self>) {
+        drain(&self.0)
+    }
+}
+
 pub struct ThreadWork<S, R> {
     argument: Box<S>,
     function: ThreadFunction<S, R>,
@@ -36,30 +100,101 @@ impl<S, R> ThreadWork<S, R> {
 
 impl<S: 'static + Send, R> Callable for ThreadWork<S, R> {
     fn call(self: Box<Self>) {
-        self.send_channel.send((self.thread_id, (self.function)(self.thread_id, *self.argument))).unwrap();
+        // if the receiver gave up waiting, say after a timeout, there's nobody left to send the result to, so it's dropped
+        let _ = self.send_channel.send((self.thread_id, (self.function)(self.thread_id, *self.argument)));
     }
 }
 
+/**
+ * Runs the work on the rayon pool.  Rayon aborts the whole process on a panic it has no handler for, so the panic is
+ * caught here instead, and the work's sender is dropped without a result, failing the gather the same way a dead thread
+ * does on the pool's own threads.
+ */
+#[cfg(feature = "rayon")]
+fn spawn_on_rayon(rayon_pool: &rayon::ThreadPool, work: Box<dyn Callable + Send>) {
+    rayon_pool.spawn(move || {
+        let _ = panic::catch_unwind(panic::AssertUnwindSafe(|| work.call()));
+    });
+}
+
+/**
+ * Sent along with each of `map`'s messages, and sends None in place of the message's result if it's dropped before
+ * being defused, which happens if the thread panics while running the message, or is already dead and never gets it.
+ * The pool keeps its own sender to `map`'s channel, so it's never disconnected, and without this gathering from it would
+ * block forever on a dead thread.
+ */
+struct ResultGuard<R> {
+    send_channel: Option<ThreadSendResultChannel<Option<R>>>,
+    thread_id: ThreadId
+}
+
+impl<R> Drop for ResultGuard<R> {
+    fn drop(&mut self) {
+        if let Some(send_channel) = self.send_channel.take() {
+            let _ = send_channel.send((self.thread_id, None));
+        }
+    }
+}
+
+/**
+ * Runs one of `map`'s messages.  Once the function has returned, the result is on its way, so the guard is defused.
+ */
+fn run_guarded<S, R>(thread_id: ThreadId, (msg, function, mut guard): (S, ThreadFunction<S, R>, ResultGuard<R>)) -> Option<R> {
+    let result = function(thread_id, msg);
+    guard.send_channel = None;
+    Some(result)
+}
+
+/**
+ * Receives the given number of results, and returns them in the order their messages were sent.  There's one result
+ * per message, not per thread, so with fewer messages than threads, the idle threads are never waited on.  A result with
+ * an index that's out of range, or repeated, fails the gather rather than panicking.
+ */
+fn gather_from<R>(receiver: &ThreadReceiveResultChannel<R>, expected_msg_count: usize) -> Result<Vec<R>, mpsc::RecvError> {
+    let mut results = (0..expected_msg_count).map(|_| None).collect::<Vec<_>>();
+    for _ in 0..expected_msg_count {
+        let ((index, _), msg) = receiver.recv()?;
+        match results.get_mut(index) {
+            Some(result @ None) => *result = Some(msg),
+            _ => return Err(mpsc::RecvError)
+        }
+    }
+
+    Ok(results.into_iter().map(|x| x.unwrap()).collect())
+}
+
 pub struct RemoteThread {
     _handle: thread::JoinHandle<()>,
     send_channel: mpsc::Sender<Box<dyn Callable + Send>>,
 }
 
 impl RemoteThread {
-    fn new() -> Self {
+    /**
+     * Spawns the thread, named after its index so it can be told apart when profiling, and pinned to the given core if
+     * there is one.  Pinning is best-effort, so if the platform doesn't support it the thread just runs unpinned.
+     */
+    fn new(index: usize, core: Option<core_affinity::CoreId>) -> Self {
         let (tx, rx) = mpsc::channel::<Box<dyn Callable + Send>>();
-        let handle = thread::spawn(move || {
+        let handle = thread::Builder::new().name(format!("prefix-scan-worker-{}", index)).spawn(move || {
+            if let Some(core) = core {
+                core_affinity::set_for_current(core);
+            }
+
             for msg in rx {
                 msg.call();
             }
-        });
+        }).unwrap();
         
         Self { _handle: handle, send_channel: tx }
     }
 
-    fn send<S: 'static + Send, R: 'static + Send>(&mut self, function: ThreadFunction<S, R>, msg: S, result_channel: ThreadSendResultChannel<R>, thread_id: ThreadId) {
+    fn send<S: 'static + Send, R: 'static + Send>(&mut self, function: ThreadFunction<S, R>, msg: S, result_channel: ThreadSendResultChannel<R>, thread_id: ThreadId) -> Result<(), ThreadDied> {
         let work = Box::new(ThreadWork::new(msg, function, result_channel, thread_id));
-        self.send_channel.send(work).unwrap();
+        self.send_channel.send(work).map_err(|_| ThreadDied)
+    }
+
+    fn steal_from(&mut self, queue: &WorkQueue) -> Result<(), ThreadDied> {
+        self.send_channel.send(Box::new(StealWork(queue.clone()))).map_err(|_| ThreadDied)
     }
 }
 
@@ -69,18 +204,38 @@ pub enum Thread {
 }
 
 impl Thread {
-    fn remote() -> Self {
-        Self::Remote(RemoteThread::new())
+    fn remote(index: usize, core: Option<core_affinity::CoreId>) -> Self {
+        Self::Remote(RemoteThread::new(index, core))
     }
 
     fn local() -> Self {
         Self::Local
     }
 
-    pub fn send<S: 'static + Send, R: 'static + Send>(&mut self, function: ThreadFunction<S, R>, msg: S, result_channel: ThreadSendResultChannel<R>, thread_id: ThreadId) {
+    /**
+     * Sends the message to the thread, or runs it right away on the local thread.  If the receiver has already been
+     * dropped, the local thread's result is dropped too, the same as a remote thread's would be.
+     */
+    pub fn send<S: 'static + Send, R: 'static + Send>(&mut self, function: ThreadFunction<S, R>, msg: S, result_channel: ThreadSendResultChannel<R>, thread_id: ThreadId) -> Result<(), ThreadDied> {
         match self {
             Self::Remote(r) => r.send(function, msg, result_channel, thread_id),
-            Self::Local => result_channel.send((thread_id, function(thread_id, msg))).unwrap()
+            Self::Local => {
+                let _ = result_channel.send((thread_id, function(thread_id, msg)));
+                Ok(())
+            }
+        }
+    }
+
+    /**
+     * Has the thread run work from the queue until it's empty.  The local thread does so right away.
+     */
+    fn steal_from(&mut self, queue: &WorkQueue) -> Result<(), ThreadDied> {
+        match self {
+            Self::Remote(r) => r.steal_from(queue),
+            Self::Local => {
+                drain(queue);
+                Ok(())
+            }
         }
     }
 }
@@ -95,13 +250,46 @@ impl<R: 'static + Send> MassReceiver<R> {
         Self { receiver, expected_msg_count }
     }
 
+    /**
+     * Waits for every result, and returns them in the order their messages were sent.  The results are indexed by the
+     * message index in their thread id, rather than by which thread ran them, so this works when threads run more than
+     * one message.
+     */
     pub fn gather(self) -> Result::<Vec<R>, mpsc::RecvError> {
+        gather_from(&self.receiver, self.expected_msg_count)
+    }
+
+    /**
+     * Yields each thread's result as soon as it arrives, rather than waiting for all of them, so that combining the
+     * results can overlap with the slower threads finishing.  The results come in the order they arrive, so each is
+     * paired with the id of the thread that produced it.  Stops early if a thread dies before sending its result.
+     */
+    pub fn gather_unordered(self) -> impl Iterator<Item = (ThreadId, R)> {
+        self.receiver.into_iter().take(self.expected_msg_count)
+    }
+
+    /**
+     * The same as `gather`, but gives up once the timeout runs out, rather than blocking forever on a stuck thread.  The
+     * timeout is for the whole gather, not for each result.  Results that arrive after it has run out are dropped.
+     */
+    pub fn gather_timeout(self, timeout: time::Duration) -> Result<Vec<R>, GatherError> {
+        let deadline = time::Instant::now() + timeout;
         let mut results = (0..self.expected_msg_count).map(|_| None).collect::<Vec<_>>();
         for _ in 0..self.expected_msg_count {
-            let ((index, _), msg) = self.receiver.recv()?;
-            match results[index] {
-                None => results[index] = Some(msg),
-                Some(_) => return Err(mpsc::RecvError)
+            let remaining = deadline.saturating_duration_since(time::Instant::now());
+            let ((index, _), msg) = match self.receiver.recv_timeout(remaining) {
+                Ok(received) => received,
+                Err(mpsc::RecvTimeoutError::Disconnected) => return Err(GatherError::Disconnected),
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    let missing = results.iter().enumerate().filter(|(_, result)| result.is_none()).map(|(index, _)| index).collect();
+                    return Err(GatherError::TimedOut { missing })
+                }
+            };
+
+            match results.get_mut(index) {
+                Some(result @ None) => *result = Some(msg),
+                Some(Some(_)) => return Err(GatherError::Duplicate { index }),
+                None => return Err(GatherError::OutOfRange { index })
             }
         }
 
@@ -110,27 +298,155 @@ impl<R: 'static + Send> MassReceiver<R> {
 }
 
 pub struct ThreadPool {
-    threads: Vec<Thread>
+    threads: Vec<Thread>,
+    work_queue: Option<WorkQueue>,
+    // `map`'s result channels, one per result type, each a `ResultChannel`
+    result_channels: Mutex<HashMap<TypeId, Box<dyn Any + Send>>>,
+    #[cfg(feature = "rayon")]
+    rayon_pool: Option<rayon::ThreadPool>
 }
 
 impl ThreadPool {
     pub fn new(num_threads: usize) -> Self {
-        let mut threads = (0..(num_threads - 1)).map(|_| Thread::remote()).collect::<Vec<_>>();
+        Self::with_affinity(num_threads, false)
+    }
+
+    /**
+     * Creates the pool, and if `pinned` is set, pins each remote thread to its own core, wrapping around if there are
+     * more threads than cores.  The local thread is the caller's, so it's left wherever it is.
+     */
+    pub fn with_affinity(num_threads: usize, pinned: bool) -> Self {
+        let core_ids = if pinned { core_affinity::get_core_ids().unwrap_or_default() } else { vec![] };
+        let core_for = |i: usize| if core_ids.is_empty() { None } else { Some(core_ids[i % core_ids.len()]) };
+
+        let mut threads = (0..(num_threads - 1)).map(|i| Thread::remote(i, core_for(i))).collect::<Vec<_>>();
         threads.push(Thread::local());
 
-        Self { threads }
+        Self { threads, work_queue: None, result_channels: Mutex::new(HashMap::new()), #[cfg(feature = "rayon")] rayon_pool: None }
+    }
+
+    /**
+     * In work stealing mode, `sendall` puts the messages in a queue shared by all of the threads, rather than handing
+     * each thread a fixed set of them.  Threads which finish early then pull more work from the queue, rather than
+     * sitting idle while the others grind through uneven chunks.
+     */
+    pub fn set_work_stealing(&mut self, work_stealing: bool) {
+        self.work_queue = if work_stealing { Some(Arc::new(crossbeam_deque::Injector::new())) } else { None };
+    }
+
+    pub fn work_stealing(&self) -> bool {
+        self.work_queue.is_some()
+    }
+
+    /**
+     * Hands the messages to a rayon pool with the same number of threads, rather than to this pool's own threads.
+     * Rayon's threads steal work from each other, so this takes precedence over work stealing mode.  The results come
+     * back through the same `MassReceiver`, so nothing else changes.  If rayon can't build its pool, this pool keeps
+     * using its own threads, which `rayon` shows.
+     */
+    #[cfg(feature = "rayon")]
+    pub fn set_rayon(&mut self, rayon: bool) {
+        self.rayon_pool = if rayon {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(self.threads.len())
+                .thread_name(|i| format!("prefix-scan-rayon-{}", i))
+                .build()
+                .ok()
+        } else {
+            None
+        };
+    }
+
+    pub fn rayon(&self) -> bool {
+        #[cfg(feature = "rayon")]
+        return self.rayon_pool.is_some();
+        #[cfg(not(feature = "rayon"))]
+        return false;
     }
 
+    /**
+     * Sends each message to its own thread, along with a thread id made of the message's index and the number of
+     * threads.  If there are more messages than threads, they wrap around the threads round-robin, and queue up behind
+     * each other on each thread.  The results are gathered in message order either way.
+     */
     pub fn sendall<S: 'static + Send, R: 'static + Send>(&mut self, msgs: Vec<S>, function: ThreadFunction<S, R>) -> MassReceiver<R> {
         let (tx, rx) = mpsc::channel();
         let msg_count = msgs.len();
+        self.dispatch(msgs, function, tx);
+        MassReceiver::new(rx, msg_count)
+    }
+
+    /**
+     * Hands out the messages the way `sendall` describes, with their results sent back on the given channel.
+     */
+    fn dispatch<S: 'static + Send, R: 'static + Send>(&mut self, msgs: Vec<S>, function: ThreadFunction<S, R>, tx: ThreadSendResultChannel<R>) {
         let num_threads = self.threads.len();
 
+        #[cfg(feature = "rayon")]
+        if let Some(rayon_pool) = &self.rayon_pool {
+            for (i, msg) in msgs.into_iter().enumerate() {
+                spawn_on_rayon(rayon_pool, Box::new(ThreadWork::new(msg, function, mpsc::Sender::clone(&tx), (i, num_threads))));
+            }
+
+            return
+        }
+
+        if let Some(queue) = &self.work_queue {
+            for (i, msg) in msgs.into_iter().enumerate() {
+                queue.push(Box::new(ThreadWork::new(msg, function, mpsc::Sender::clone(&tx), (i, num_threads))));
+            }
+
+            // the local thread is last, so the remote threads are all woken up before it starts draining the queue too.  a
+            // dead thread just can't help, since the rest of the threads drain the whole queue without it
+            for thread in self.threads.iter_mut() {
+                let _ = thread.steal_from(queue);
+            }
+
+            return
+        }
+
+        // a message sent to a dead thread is dropped along with its sender, so gathering fails once the rest are in,
+        // rather than panicking here
         for (i, msg) in msgs.into_iter().enumerate() {
-            self.threads[i].send(function, msg, mpsc::Sender::clone(&tx), (i, num_threads));
+            let _ = self.threads[i % num_threads].send(function, msg, mpsc::Sender::clone(&tx), (i, num_threads));
         }
+    }
 
-        MassReceiver::new(rx, msg_count)
+    /**
+     * Sends the messages the same way as `sendall`, and gathers their results in order.  If any thread fails to send its
+     * result, the whole map fails with `ScanError::FailedThreadInGather`.  Unlike `sendall`, which makes a new channel
+     * every time, the result channel is kept for the next map with the same result type, so that scans that map over
+     * the pool every step, like the hillis steel scan, don't allocate a channel for each one.  A failed map might leave
+     * late results in its channel, so it's thrown away instead.
+     */
+    pub fn map<S: 'static + Send, R: 'static + Send>(&mut self, msgs: Vec<S>, function: ThreadFunction<S, R>) -> Result<Vec<R>, ScanError> {
+        let (tx, rx) = self.take_result_channel::<R>();
+        let msg_count = msgs.len();
+        let num_threads = self.threads.len();
+
+        let msgs = msgs.into_iter().enumerate().map(|(i, msg)| {
+            (msg, function, ResultGuard { send_channel: Some(tx.clone()), thread_id: (i, num_threads) })
+        }).collect::<Vec<_>>();
+        self.dispatch(msgs, run_guarded::<S, R>, tx.clone());
+
+        let results = gather_from(&rx, msg_count).ok()
+            .and_then(|results| results.into_iter().collect::<Option<Vec<_>>>())
+            .ok_or(ScanError::FailedThreadInGather)?;
+        self.return_result_channel((tx, rx));
+        Ok(results)
+    }
+
+    fn take_result_channel<R: 'static + Send>(&mut self) -> ResultChannel<R> {
+        let result_channels = self.result_channels.get_mut().unwrap_or_else(|poisoned| poisoned.into_inner());
+        result_channels.remove(&TypeId::of::<R>())
+            .and_then(|channel| channel.downcast::<ResultChannel<R>>().ok())
+            .map(|channel| *channel)
+            .unwrap_or_else(mpsc::channel)
+    }
+
+    fn return_result_channel<R: 'static + Send>(&mut self, channel: ResultChannel<R>) {
+        let result_channels = self.result_channels.get_mut().unwrap_or_else(|poisoned| poisoned.into_inner());
+        result_channels.insert(TypeId::of::<R>(), Box::new(channel));
     }
 
     pub fn num_threads(&self) -> usize {
@@ -141,12 +457,32 @@ impl ThreadPool {
         let (tx, rx) = mpsc::channel();
         let num_threads = self.threads.len();
 
+        #[cfg(feature = "rayon")]
+        if let Some(rayon_pool) = &self.rayon_pool {
+            for i in 0..num_threads {
+                spawn_on_rayon(rayon_pool, Box::new(ThreadWork::new(msg.clone(), function, mpsc::Sender::clone(&tx), (i, num_threads))));
+            }
+
+            return MassReceiver::new(rx, num_threads)
+        }
+
         for (i, thread) in self.threads.iter_mut().enumerate() {
-            thread.send(function, msg.clone(), mpsc::Sender::clone(&tx), (i, num_threads));
+            let _ = thread.send(function, msg.clone(), mpsc::Sender::clone(&tx), (i, num_threads));
         }
 
         MassReceiver::new(rx, self.threads.len())
     }
+
+    /**
+     * The same as `broadcast`, but each thread borrows the data rather than getting its own clone, so it needn't be
+     * `'static`, `Clone`, or wrapped in an `Arc`.  It runs on the pool's own threads, through the executor's
+     * `map_scoped`, which doesn't return until every thread is done with the borrow.  The results are in thread order,
+     * and a thread that panics, the local thread included, fails it with `ScanError::FailedThreadInGather`.
+     */
+    pub fn broadcast_scoped<'env, S: Send + Sync + ?Sized, R: Send>(&mut self, data: &'env S, function: impl Fn(ThreadId, &'env S) -> R + Sync) -> Result<Vec<R>, ScanError> {
+        let msgs = vec![data; self.threads.len()];
+        (self as &mut dyn executor::ScanExecutor).map_scoped(msgs, function)
+    }
 }
 
 impl Index<usize> for ThreadPool {
@@ -178,4 +514,233 @@ mod test {
 
         assert_eq!(result, 1 + 4 + 9 + 16);
     }
+
+    #[test]
+    fn map_test() {
+        let numbers = vec![1, 2, 3, 4];
+        let mut pool = thread_pool::ThreadPool::new(4);
+
+        let result = pool.map(numbers, |_, x: u64| x * x).unwrap();
+        assert_eq!(result, vec![1, 4, 9, 16]);
+        assert_eq!(result.iter().sum::<u64>(), 1 + 4 + 9 + 16);
+    }
+
+    #[test]
+    fn map_reuse_test() {
+        let mut pool = thread_pool::ThreadPool::new(4);
+
+        // the same channel is reused by each map, and every result still comes back to the message that produced it, even
+        // with the threads finishing out of order, and different numbers of messages each time
+        for round in 0..20u64 {
+            let msgs = (0..(round as usize % 7 + 1) * 3).map(|i| (i as u64, round)).collect::<Vec<_>>();
+            let expected = msgs.iter().enumerate().map(|(i, (x, round))| (i, x * 10 + round)).collect::<Vec<_>>();
+            let results = pool.map(msgs, |(index, _), (x, round): (u64, u64)| {
+                std::thread::sleep(std::time::Duration::from_micros((index as u64 * 37 + round) % 200));
+                (index, x * 10 + round)
+            }).unwrap();
+            assert_eq!(results, expected);
+        }
+        assert_eq!(pool.result_channels.lock().unwrap().len(), 1);
+
+        // a different result type gets its own channel
+        assert_eq!(pool.map(vec![1u32, 2], |_, x| x as u8).unwrap(), vec![1, 2]);
+        assert_eq!(pool.result_channels.lock().unwrap().len(), 2);
+
+        // a map that fails throws its channel away, and the next one doesn't see any of its results
+        let failed = pool.map(vec![0u64, 1, 2, 3], |(index, _), x| if index == 0 { panic!("thread failed") } else { (index, x) });
+        assert!(matches!(failed, Err(crate::prefix_scans::ScanError::FailedThreadInGather)));
+        assert_eq!(pool.result_channels.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn broadcast_scoped_test() {
+        let numbers = (1..=8).collect::<Vec<u64>>();
+        let mut pool = thread_pool::ThreadPool::new(4);
+
+        // each thread sums its own quarter of the borrowed slice
+        let sums = pool.broadcast_scoped(&numbers[..], |(index, num_threads), numbers| {
+            let chunk = numbers.len() / num_threads;
+            numbers[index * chunk..(index + 1) * chunk].iter().sum::<u64>()
+        }).unwrap();
+        assert_eq!(sums, vec![3, 7, 11, 15]);
+
+        let failed = pool.broadcast_scoped(&numbers, |(index, _), _| if index == 1 { panic!("thread failed") } else { index });
+        assert!(matches!(failed, Err(crate::prefix_scans::ScanError::FailedThreadInGather)));
+        // the last index runs on the calling thread, whose panic fails the broadcast rather than unwinding out of it
+        let failed = pool.broadcast_scoped(&numbers, |(index, num_threads), _| if index == num_threads - 1 { panic!("local thread failed") } else { index });
+        assert!(matches!(failed, Err(crate::prefix_scans::ScanError::FailedThreadInGather)));
+        assert_eq!(pool.broadcast_scoped(&numbers, |(index, _), _| index).unwrap(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn more_messages_than_threads_test() {
+        let mut pool = thread_pool::ThreadPool::new(4);
+        let result = pool.sendall((0..10).collect(), |(index, num_threads), x: u64| {
+            assert_eq!(num_threads, 4);
+            (index, x * x)
+        }).gather().unwrap();
+        assert_eq!(result, (0..10).map(|x| (x as usize, x * x)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn fewer_messages_than_threads_test() {
+        let mut pool = thread_pool::ThreadPool::new(8);
+        let result = pool.sendall(vec![3u64, 4], |(index, num_threads), x| (index, num_threads, x * x)).gather().unwrap();
+        assert_eq!(result, vec![(0, 8, 9), (1, 8, 16)]);
+        assert_eq!(pool.map(vec![3u64, 4], |_, x| x + 1).unwrap(), vec![4, 5]);
+        assert_eq!(pool.map(vec![], |_, x: u64| x).unwrap(), Vec::<u64>::new());
+
+        // a result with an index past the messages sent, say from sending to the threads directly, fails the gather
+        let (tx, rx) = std::sync::mpsc::channel();
+        pool[7].send(|_, x: u64| x, 1, tx.clone(), (7, 8)).unwrap();
+        assert!(thread_pool::MassReceiver::new(rx, 2).gather().is_err());
+        let (tx, rx) = std::sync::mpsc::channel();
+        pool[7].send(|_, x: u64| x, 1, tx, (7, 8)).unwrap();
+        let result = thread_pool::MassReceiver::new(rx, 2).gather_timeout(std::time::Duration::from_secs(5));
+        assert_eq!(result, Err(thread_pool::GatherError::OutOfRange { index: 7 }));
+
+        // a divide and conquer scan with chunks smaller than the pool leaves most of the threads idle
+        let list = (0..10).collect::<Vec<u64>>();
+        let baseline = crate::prefix_scans::baseline::sequential_scan_no_simd(list.clone(), |a, b| a + b).unwrap();
+        let mut scanner = crate::prefix_scans::Scanner::new().with_threads(8).with_cache_chunk_length(2).with_sequential_length(0);
+        assert_eq!(scanner.divide_and_conquer_scan(list).unwrap(), baseline);
+    }
+
+    #[test]
+    fn oversubscribed_pool_test() {
+        let mut pool = thread_pool::ThreadPool::new(4);
+        // uneven sleeps make the results arrive out of order, but they should still be gathered in submission order
+        let result = pool.sendall((0..100).collect(), |(index, _), x: u64| {
+            std::thread::sleep(std::time::Duration::from_micros((index as u64 * 37) % 500));
+            x + 1
+        }).gather().unwrap();
+        assert_eq!(result, (1..101).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn work_stealing_test() {
+        let mut pool = thread_pool::ThreadPool::new(4);
+        pool.set_work_stealing(true);
+        assert!(pool.work_stealing());
+
+        // every eighth task is much slower, so the threads that don't get one should pick up the slack
+        let result = pool.sendall((0..64).collect(), |(index, _), x: u64| {
+            let millis = if index % 8 == 0 { 20 } else { 1 };
+            std::thread::sleep(std::time::Duration::from_millis(millis));
+            x * 2
+        }).gather().unwrap();
+        assert_eq!(result, (0..64).map(|x| x * 2).collect::<Vec<_>>());
+
+        pool.set_work_stealing(false);
+        assert_eq!(pool.sendall(vec![1, 2, 3], |_, x: u64| x).gather().unwrap(), vec![1, 2, 3]);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn rayon_test() {
+        let mut pool = thread_pool::ThreadPool::new(4);
+        pool.set_rayon(true);
+        assert!(pool.rayon());
+
+        // more messages than threads, gathered back in message order
+        let squares = pool.sendall((0..100).collect::<Vec<u64>>(), |_, x| x * x).gather().unwrap();
+        assert_eq!(squares, (0..100).map(|x| x * x).collect::<Vec<u64>>());
+
+        let names = pool.broadcast((), |_, _| std::thread::current().name().unwrap().to_string()).gather().unwrap();
+        assert_eq!(names.len(), 4);
+        assert!(names.iter().all(|name| name.starts_with("prefix-scan-rayon-")));
+
+        pool.set_rayon(false);
+        assert!(!pool.rayon());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn rayon_dead_thread_test() {
+        let mut pool = thread_pool::ThreadPool::new(2);
+        pool.set_rayon(true);
+
+        // a panicking message fails its gather or map, rather than taking the process down with it
+        assert!(pool.sendall(vec![0u64, 1], |(index, _), x| if index == 0 { panic!("dead worker") } else { x }).gather().is_err());
+        assert!(pool.broadcast(0u64, |(index, _), x| if index == 1 { panic!("dead worker") } else { x }).gather().is_err());
+        let failed = pool.map(vec![0u64, 1, 2], |(index, _), x| if index == 2 { panic!("dead worker") } else { x });
+        assert!(matches!(failed, Err(crate::prefix_scans::ScanError::FailedThreadInGather)));
+
+        // and rayon's threads carry on afterwards
+        assert_eq!(pool.map(vec![1u64, 2, 3], |_, x| x * 2).unwrap(), vec![2, 4, 6]);
+    }
+
+    #[test]
+    fn dead_thread_test() {
+        let mut pool = thread_pool::ThreadPool::new(2);
+        // the receiver is dropped right away, so nobody is left to take either result
+        drop(pool.sendall(vec![1u64, 2], |_, x| x));
+
+        // panicking in a message kills the remote thread, which is the first one
+        assert!(pool.sendall(vec![0u64, 1], |(index, _), x| if index == 0 { panic!("dead worker") } else { x }).gather().is_err());
+        // the gather can fail while the thread is still unwinding, so give it a moment to finish dying
+        let died = (0..1000).any(|_| {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+            pool[0].send(|_, x: u64| x, 1, std::sync::mpsc::channel().0, (0, 2)) == Err(thread_pool::ThreadDied)
+        });
+        assert!(died);
+        assert_eq!(pool[1].send(|_, x: u64| x, 1, std::sync::mpsc::channel().0, (1, 2)), Ok(()));
+
+        // sending to the dead thread fails the gather, rather than panicking the caller
+        assert!(pool.sendall(vec![1u64, 2], |_, x| x).gather().is_err());
+        assert!(pool.broadcast(1u64, |_, x| x).gather().is_err());
+    }
+
+    #[test]
+    fn thread_names_test() {
+        let mut pool = thread_pool::ThreadPool::new(3);
+        let names = pool.broadcast((), |_, _| std::thread::current().name().map(String::from)).gather().unwrap();
+        assert_eq!(names[0].as_deref(), Some("prefix-scan-worker-0"));
+        assert_eq!(names[1].as_deref(), Some("prefix-scan-worker-1"));
+        // the local thread is the caller's, so it keeps the caller's name
+        assert_eq!(names[2].as_deref(), std::thread::current().name());
+    }
+
+    #[test]
+    fn pinned_pool_test() {
+        let mut pool = thread_pool::ThreadPool::with_affinity(4, true);
+        let result = pool.broadcast((), |(index, _), _| index).gather().unwrap();
+        assert_eq!(result, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn gather_unordered_test() {
+        let mut pool = thread_pool::ThreadPool::new(4);
+        // the earlier threads sleep the longest, so the results arrive roughly in reverse
+        let results = pool.sendall(vec![60, 40, 20, 0], |(index, _), millis: u64| {
+            std::thread::sleep(std::time::Duration::from_millis(millis));
+            index * 10
+        }).gather_unordered();
+
+        let mut results = results.map(|((index, num_threads), result)| {
+            assert_eq!(num_threads, 4);
+            assert_eq!(result, index * 10);
+            index
+        }).collect::<Vec<_>>();
+        results.sort();
+        assert_eq!(results, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn gather_timeout_test() {
+        let mut pool = thread_pool::ThreadPool::new(3);
+        let sleepy = |(index, _), millis: u64| {
+            if index == 0 {
+                std::thread::sleep(std::time::Duration::from_millis(millis));
+            }
+            index
+        };
+
+        let result = pool.sendall(vec![500, 500, 500], sleepy).gather_timeout(std::time::Duration::from_millis(50));
+        assert_eq!(result, Err(thread_pool::GatherError::TimedOut { missing: vec![0] }));
+
+        // the stuck thread's late result is dropped, and the pool is still usable afterwards
+        let result = pool.sendall(vec![0, 0, 0], sleepy).gather_timeout(std::time::Duration::from_secs(5));
+        assert_eq!(result, Ok(vec![0, 1, 2]));
+    }
 }
\ No newline at end of file